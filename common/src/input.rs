@@ -0,0 +1,276 @@
+use std::collections::{BTreeMap, HashSet};
+
+use crate::Dimentions;
+
+// Keys by their position on a US keyboard, whatever the layout.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Key {
+    A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+    Num0, Num1, Num2, Num3, Num4, Num5, Num6, Num7, Num8, Num9,
+    F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12,
+    Up, Down, Left, Right,
+    Space, Enter, Escape, Tab, Backspace, Insert, Delete, Home, End, PageUp, PageDown, CapsLock,
+    LeftShift, RightShift, LeftControl, RightControl, LeftAlt, RightAlt, LeftSuper, RightSuper,
+    Minus, Equal, LeftBracket, RightBracket, Backslash, Semicolon, Apostrophe, Comma, Period, Slash, GraveAccent,
+    // Any key without a variant of its own
+    Unknown,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+    // The extra buttons, numbered from 4
+    Other(u8),
+}
+
+// The buttons of a standard gamepad, named after the Xbox controller layout.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GamepadButton {
+    A, B, X, Y,
+    LeftBumper, RightBumper,
+    Back, Start, Guide,
+    LeftThumb, RightThumb,
+    DpadUp, DpadRight, DpadDown, DpadLeft,
+}
+
+// Sticks go from -1 to 1, +y is down. Triggers go from 0, released, to 1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GamepadAxis {
+    LeftX,
+    LeftY,
+    RightX,
+    RightY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+const GAMEPAD_AXIS_COUNT: usize = 6;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum InputEvent {
+    KeyPressed(Key),
+    KeyReleased(Key),
+    MouseButtonPressed(MouseButton),
+    MouseButtonReleased(MouseButton),
+    // In pixels from the top left corner of the window
+    CursorMoved { x: f32, y: f32 },
+    // In lines, positive y away from the user
+    Scrolled { x: f32, y: f32 },
+    // Typed text, with the keyboard layout and modifiers applied
+    Text(char),
+    Focused(bool),
+    // Gamepads are identified by the slot they were connected to, which is reused once they disconnect
+    GamepadConnected { gamepad: u8, name: String },
+    GamepadDisconnected { gamepad: u8 },
+    GamepadButtonPressed { gamepad: u8, button: GamepadButton },
+    GamepadButtonReleased { gamepad: u8, button: GamepadButton },
+    // The value after the dead zone
+    GamepadAxisMoved { gamepad: u8, axis: GamepadAxis, value: f32 },
+    // The new framebuffer size, in pixels
+    Resized(Dimentions),
+    CloseRequested,
+}
+
+// The input accumulated from the events, queried once per frame.
+// The just pressed and released keys and buttons, the cursor delta, the scroll and the text are cleared by end_frame.
+// Gamepads keep their state while the window is not focused.
+#[derive(Clone, Debug, Default)]
+pub struct InputState {
+    pressed_keys: HashSet<Key>,
+    just_pressed_keys: HashSet<Key>,
+    just_released_keys: HashSet<Key>,
+    pressed_mouse_buttons: HashSet<MouseButton>,
+    just_pressed_mouse_buttons: HashSet<MouseButton>,
+    just_released_mouse_buttons: HashSet<MouseButton>,
+    cursor_position: Option<(f32, f32)>,
+    cursor_delta: (f32, f32),
+    scroll_delta: (f32, f32),
+    text: String,
+    focused: bool,
+    gamepads: BTreeMap<u8, GamepadState>,
+}
+
+impl InputState {
+    pub fn handle_event(&mut self, event: &InputEvent) {
+        match *event {
+            InputEvent::KeyPressed(key) => {
+                if self.pressed_keys.insert(key) {
+                    self.just_pressed_keys.insert(key);
+                }
+            }
+            InputEvent::KeyReleased(key) => {
+                if self.pressed_keys.remove(&key) {
+                    self.just_released_keys.insert(key);
+                }
+            }
+            InputEvent::MouseButtonPressed(button) => {
+                if self.pressed_mouse_buttons.insert(button) {
+                    self.just_pressed_mouse_buttons.insert(button);
+                }
+            }
+            InputEvent::MouseButtonReleased(button) => {
+                if self.pressed_mouse_buttons.remove(&button) {
+                    self.just_released_mouse_buttons.insert(button);
+                }
+            }
+            InputEvent::CursorMoved { x, y } => {
+                // The first position has nothing to move from
+                if let Some((last_x, last_y)) = self.cursor_position {
+                    self.cursor_delta.0 += x - last_x;
+                    self.cursor_delta.1 += y - last_y;
+                }
+                self.cursor_position = Some((x, y));
+            }
+            InputEvent::Scrolled { x, y } => {
+                self.scroll_delta.0 += x;
+                self.scroll_delta.1 += y;
+            }
+            InputEvent::Text(character) => self.text.push(character),
+            InputEvent::Focused(focused) => {
+                self.focused = focused;
+                // The release events go to the window that has the focus, the keys would stay pressed forever
+                if !focused {
+                    self.just_released_keys.extend(self.pressed_keys.drain());
+                    self.just_released_mouse_buttons.extend(self.pressed_mouse_buttons.drain());
+                }
+            }
+            InputEvent::GamepadConnected { gamepad, ref name } => {
+                self.gamepads.insert(gamepad, GamepadState::new(name.clone()));
+            }
+            InputEvent::GamepadDisconnected { gamepad } => {
+                self.gamepads.remove(&gamepad);
+            }
+            InputEvent::GamepadButtonPressed { gamepad, button } => {
+                if let Some(gamepad) = self.gamepads.get_mut(&gamepad) {
+                    if gamepad.pressed_buttons.insert(button) {
+                        gamepad.just_pressed_buttons.insert(button);
+                    }
+                }
+            }
+            InputEvent::GamepadButtonReleased { gamepad, button } => {
+                if let Some(gamepad) = self.gamepads.get_mut(&gamepad) {
+                    if gamepad.pressed_buttons.remove(&button) {
+                        gamepad.just_released_buttons.insert(button);
+                    }
+                }
+            }
+            InputEvent::GamepadAxisMoved { gamepad, axis, value } => {
+                if let Some(gamepad) = self.gamepads.get_mut(&gamepad) {
+                    gamepad.axes[axis as usize] = value;
+                }
+            }
+            InputEvent::Resized(_) | InputEvent::CloseRequested => {}
+        }
+    }
+
+    pub fn end_frame(&mut self) {
+        self.just_pressed_keys.clear();
+        self.just_released_keys.clear();
+        self.just_pressed_mouse_buttons.clear();
+        self.just_released_mouse_buttons.clear();
+        self.cursor_delta = (0.0, 0.0);
+        self.scroll_delta = (0.0, 0.0);
+        self.text.clear();
+        for gamepad in self.gamepads.values_mut() {
+            gamepad.just_pressed_buttons.clear();
+            gamepad.just_released_buttons.clear();
+        }
+    }
+
+    pub fn is_key_pressed(&self, key: Key) -> bool {
+        self.pressed_keys.contains(&key)
+    }
+
+    pub fn is_key_just_pressed(&self, key: Key) -> bool {
+        self.just_pressed_keys.contains(&key)
+    }
+
+    pub fn is_key_just_released(&self, key: Key) -> bool {
+        self.just_released_keys.contains(&key)
+    }
+
+    pub fn is_mouse_button_pressed(&self, button: MouseButton) -> bool {
+        self.pressed_mouse_buttons.contains(&button)
+    }
+
+    pub fn is_mouse_button_just_pressed(&self, button: MouseButton) -> bool {
+        self.just_pressed_mouse_buttons.contains(&button)
+    }
+
+    pub fn is_mouse_button_just_released(&self, button: MouseButton) -> bool {
+        self.just_released_mouse_buttons.contains(&button)
+    }
+
+    // None until the cursor moves over the window
+    pub fn cursor_position(&self) -> Option<(f32, f32)> {
+        self.cursor_position
+    }
+
+    pub fn cursor_delta(&self) -> (f32, f32) {
+        self.cursor_delta
+    }
+
+    pub fn scroll_delta(&self) -> (f32, f32) {
+        self.scroll_delta
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    // The connected gamepads, by slot
+    pub fn gamepads(&self) -> impl Iterator<Item = (u8, &GamepadState)> {
+        self.gamepads.iter().map(|(gamepad, state)| (*gamepad, state))
+    }
+
+    pub fn gamepad(&self, gamepad: u8) -> Option<&GamepadState> {
+        self.gamepads.get(&gamepad)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct GamepadState {
+    name: String,
+    pressed_buttons: HashSet<GamepadButton>,
+    just_pressed_buttons: HashSet<GamepadButton>,
+    just_released_buttons: HashSet<GamepadButton>,
+    axes: [f32; GAMEPAD_AXIS_COUNT],
+}
+
+impl GamepadState {
+    fn new(name: String) -> GamepadState {
+        GamepadState {
+            name,
+            pressed_buttons: HashSet::new(),
+            just_pressed_buttons: HashSet::new(),
+            just_released_buttons: HashSet::new(),
+            axes: [0.0; GAMEPAD_AXIS_COUNT],
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn is_button_pressed(&self, button: GamepadButton) -> bool {
+        self.pressed_buttons.contains(&button)
+    }
+
+    pub fn is_button_just_pressed(&self, button: GamepadButton) -> bool {
+        self.just_pressed_buttons.contains(&button)
+    }
+
+    pub fn is_button_just_released(&self, button: GamepadButton) -> bool {
+        self.just_released_buttons.contains(&button)
+    }
+
+    pub fn axis(&self, axis: GamepadAxis) -> f32 {
+        self.axes[axis as usize]
+    }
+}
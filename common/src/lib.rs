@@ -1,3 +1,8 @@
+pub mod input;
+pub mod window;
+pub mod window_adapter;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Dimentions {
     pub width: u32,
     pub height: u32,
@@ -0,0 +1,61 @@
+use crate::Dimentions;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WindowMode {
+    Windowed,
+    // Changes the video mode of the monitor to the size of the window, monitors are numbered from 0, the primary one.
+    Fullscreen { monitor: usize },
+    // Covers the monitor at its current video mode, without the mode switch of Fullscreen.
+    BorderlessFullscreen { monitor: usize },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CursorMode {
+    Normal,
+    // Hidden while over the window.
+    Hidden,
+    // Hidden and locked to the window, for mouse look, the cursor position keeps moving.
+    Disabled,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WindowIcon {
+    pub width: u32,
+    pub height: u32,
+    // 4 bytes per pixel, row by row from the top left.
+    pub rgba: Vec<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct WindowConfig {
+    pub title: String,
+    // In screen coordinates, which may differ from pixels on high DPI screens.
+    pub size: Dimentions,
+    pub mode: WindowMode,
+    pub resizable: bool,
+    // Whether the windowed window has a title bar and borders.
+    pub decorated: bool,
+    pub min_size: Option<Dimentions>,
+    pub max_size: Option<Dimentions>,
+    pub icon: Option<WindowIcon>,
+    pub cursor_mode: CursorMode,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        WindowConfig {
+            title: String::new(),
+            size: Dimentions {
+                width: 800,
+                height: 600,
+            },
+            mode: WindowMode::Windowed,
+            resizable: true,
+            decorated: true,
+            min_size: None,
+            max_size: None,
+            icon: None,
+            cursor_mode: CursorMode::Normal,
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use std::sync::Arc;
+
+use raw_window_handle::{HasDisplayHandle, HasWindowHandle};
+
+use crate::Dimentions;
+use crate::input::{InputEvent, InputState};
+use crate::window::{CursorMode, WindowMode};
+
+// The raw handles the graphics create their surface from, implemented by the windows of every backend.
+pub trait WindowHandle: HasWindowHandle + HasDisplayHandle + Send + Sync {}
+
+impl<T> WindowHandle for T where T: HasWindowHandle + HasDisplayHandle + Send + Sync {}
+
+// A window and its input, whatever the windowing library, for games that own their loop.
+pub trait WindowAdapter {
+    // Starts a new frame of the input state, then updates it with the events received since the last call,
+    // which are also passed to handle_event.
+    fn poll_events(&mut self, handle_event: &mut dyn FnMut(&InputEvent));
+
+    fn get_input_state(&self) -> &InputState;
+
+    // False once the window was asked to close, by the user or by request_close.
+    fn should_loop_continue(&mut self) -> bool;
+
+    fn request_close(&mut self);
+
+    // In screen coordinates
+    fn get_window_size(&self) -> Dimentions;
+
+    // The framebuffer size is in pixels and may differ from the window size on high DPI screens,
+    // this is the size the surface should be configured with.
+    fn get_framebuffer_size(&self) -> Dimentions;
+
+    // For the surface, which must be dropped before the adapter as the handles don't keep the window alive.
+    fn get_window_handle(&self) -> Arc<dyn WindowHandle>;
+
+    fn set_title(&mut self, title: &str) -> anyhow::Result<()>;
+
+    fn set_cursor_mode(&mut self, cursor_mode: CursorMode) -> anyhow::Result<()>;
+
+    // Switches between windowed and fullscreen, the window gets back its position and size when leaving fullscreen.
+    fn set_window_mode(&mut self, window_mode: WindowMode) -> anyhow::Result<()>;
+
+    fn get_window_mode(&self) -> WindowMode;
+}
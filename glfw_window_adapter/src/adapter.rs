@@ -1,14 +1,31 @@
 use anyhow::Context;
 use common::Dimentions;
+use common::input::{InputEvent, InputState};
+use common::window::{CursorMode, WindowConfig, WindowIcon, WindowMode};
+use common::window_adapter::{WindowAdapter, WindowHandle};
+
+use crate::gamepad::{DEFAULT_GAMEPAD_DEAD_ZONE, GamepadPoller};
+use crate::input::translate_event;
+use crate::window_handle::GLFWWindowHandle;
 
 pub struct GLFWAdapter {
     glfw: glfw::Glfw,
-    window: std::sync::Arc<glfw::PWindow>,
-    events: glfw::GlfwReceiver<(f64, glfw::WindowEvent)>
+    window: glfw::PWindow,
+    // What the surface gets instead of the window
+    window_handle: std::sync::Arc<GLFWWindowHandle>,
+    events: glfw::GlfwReceiver<(f64, glfw::WindowEvent)>,
+    input_state: InputState,
+    gamepad_poller: GamepadPoller,
+    window_mode: WindowMode,
+    // The size of the exclusive fullscreen mode
+    fullscreen_size: Dimentions,
+    // Where the window goes back to when leaving fullscreen
+    windowed_position: (i32, i32),
+    windowed_size: Dimentions,
 }
 
 impl GLFWAdapter {
-    pub fn new(title: &str) -> anyhow::Result<GLFWAdapter> {
+    pub fn new(config: &WindowConfig) -> anyhow::Result<GLFWAdapter> {
         use glfw::fail_on_errors;
         log::info!("Initializing GLFW.");
 
@@ -19,43 +36,229 @@ impl GLFWAdapter {
 
         // Disable default OpenGL
         glfw.window_hint(glfw::WindowHint::ClientApi(glfw::ClientApiHint::NoApi));
+        glfw.window_hint(glfw::WindowHint::Resizable(config.resizable));
+        glfw.window_hint(glfw::WindowHint::Decorated(config.decorated));
 
         log::info!("Creating GLFW window.");
 
+        // Created windowed, the fullscreen modes are applied like they are at runtime
         let (mut window, events) = glfw
-            .create_window(800, 600, title, glfw::WindowMode::Windowed)
+            .create_window(config.size.width, config.size.height, &config.title, glfw::WindowMode::Windowed)
             .context("Failed to create GLFW window.")?;
 
         log::info!("GLFW window created.");
 
+        window.set_size_limits(
+            config.min_size.map(|size| size.width),
+            config.min_size.map(|size| size.height),
+            config.max_size.map(|size| size.width),
+            config.max_size.map(|size| size.height),
+        );
+        if let Some(icon) = &config.icon {
+            window.set_icon_from_pixels(vec![Self::make_pixel_image(icon)?]);
+        }
+
         window.set_key_polling(true);
-        
-        Ok(GLFWAdapter {
+        window.set_framebuffer_size_polling(true);
+        // For the camera controllers
+        window.set_cursor_pos_polling(true);
+        window.set_scroll_polling(true);
+        window.set_mouse_button_polling(true);
+        window.set_char_polling(true);
+        window.set_focus_polling(true);
+        window.set_close_polling(true);
+
+        let mut input_state = InputState::default();
+        input_state.handle_event(&InputEvent::Focused(window.is_focused()));
+
+        let windowed_position = window.get_pos();
+        let window_handle = std::sync::Arc::new(GLFWWindowHandle::new(&window)?);
+        let mut adapter = GLFWAdapter {
             glfw,
-            window: std::sync::Arc::new(window),
+            window,
+            window_handle,
             events,
+            input_state,
+            gamepad_poller: GamepadPoller::new(DEFAULT_GAMEPAD_DEAD_ZONE),
+            window_mode: WindowMode::Windowed,
+            fullscreen_size: config.size,
+            windowed_position,
+            windowed_size: config.size,
+        };
+        adapter.set_cursor_mode(config.cursor_mode)?;
+        adapter.set_window_mode(config.mode)?;
+        Ok(adapter)
+    }
+
+    fn make_pixel_image(icon: &WindowIcon) -> anyhow::Result<glfw::PixelImage> {
+        if icon.rgba.len() != (icon.width * icon.height * 4) as usize {
+            return Err(anyhow::anyhow!(
+                "The window icon has {} bytes, expected {} for {}x{} RGBA pixels.",
+                icon.rgba.len(), icon.width * icon.height * 4, icon.width, icon.height,
+            ));
+        }
+        Ok(glfw::PixelImage {
+            width: icon.width,
+            height: icon.height,
+            // GLFW reads the pixels as bytes, in memory order
+            pixels: icon.rgba
+                .chunks_exact(4)
+                .map(|pixel| u32::from_ne_bytes([pixel[0], pixel[1], pixel[2], pixel[3]]))
+                .collect(),
         })
     }
 
-    pub fn poll_events<F>(&mut self, handle_event: F) where F: Fn(&glfw::Window, glfw::WindowEvent) {
+    // Between 0 and 1, applies to the sticks and the triggers.
+    pub fn set_gamepad_dead_zone(&mut self, dead_zone: f32) {
+        self.gamepad_poller.set_dead_zone(dead_zone.clamp(0.0, 0.99));
+    }
+
+    // Adds or replaces gamepad mappings, in the SDL_GameControllerDB format, one per line.
+    // GLFW comes with the mappings of the common gamepads, this is for the others.
+    pub fn load_gamepad_mappings(&mut self, mappings: &str) -> anyhow::Result<()> {
+        use glfw::fail_on_errors;
+        // GLFW reports invalid mappings through the error callback, which panics, it is replaced for the call
+        let errors = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let callback_errors = errors.clone();
+        self.glfw.set_error_callback(move |_, description| callback_errors.borrow_mut().push(description));
+        let parsed = self.glfw.update_gamepad_mappings(mappings);
+        self.glfw.set_error_callback(fail_on_errors!());
+
+        if !parsed {
+            return Err(anyhow::anyhow!("Failed to parse the gamepad mappings: {}", errors.borrow().join(", ")));
+        }
+        Ok(())
+    }
+
+    pub fn get_window(&self) -> &glfw::PWindow {
+        &self.window
+    }
+}
+
+impl WindowAdapter for GLFWAdapter {
+    // The changes of the gamepads are polled with the events.
+    fn poll_events(&mut self, handle_event: &mut dyn FnMut(&InputEvent)) {
+        self.input_state.end_frame();
         self.glfw.poll_events();
         for (_, event) in glfw::flush_messages(&self.events) {
-            handle_event(&self.window, event);
+            if let Some(event) = translate_event(&event) {
+                self.input_state.handle_event(&event);
+                handle_event(&event);
+            }
         }
+        let input_state = &mut self.input_state;
+        self.gamepad_poller.poll(&self.glfw, |event| {
+            input_state.handle_event(&event);
+            handle_event(&event);
+        });
+    }
+
+    fn get_input_state(&self) -> &InputState {
+        &self.input_state
     }
 
-    pub fn should_loop_continue(&mut self) -> bool {
+    fn should_loop_continue(&mut self) -> bool {
         !self.window.should_close()
     }
 
-    pub fn get_window(&self) -> std::sync::Arc<glfw::PWindow> {
-        self.window.clone()
+    fn request_close(&mut self) {
+        self.window.set_should_close(true);
     }
 
-    pub fn get_window_size(&self) -> Dimentions {
+    fn get_window_size(&self) -> Dimentions {
         Dimentions {
             width: self.window.get_size().0 as _,
             height: self.window.get_size().1 as _,
         }
     }
-}
\ No newline at end of file
+
+    fn get_framebuffer_size(&self) -> Dimentions {
+        Dimentions {
+            width: self.window.get_framebuffer_size().0 as _,
+            height: self.window.get_framebuffer_size().1 as _,
+        }
+    }
+
+    fn get_window_handle(&self) -> std::sync::Arc<dyn WindowHandle> {
+        self.window_handle.clone()
+    }
+
+    fn set_title(&mut self, title: &str) -> anyhow::Result<()> {
+        self.window.set_title(title);
+        Ok(())
+    }
+
+    fn set_cursor_mode(&mut self, cursor_mode: CursorMode) -> anyhow::Result<()> {
+        self.window.set_cursor_mode(match cursor_mode {
+            CursorMode::Normal => glfw::CursorMode::Normal,
+            CursorMode::Hidden => glfw::CursorMode::Hidden,
+            CursorMode::Disabled => glfw::CursorMode::Disabled,
+        });
+        Ok(())
+    }
+
+    fn set_window_mode(&mut self, window_mode: WindowMode) -> anyhow::Result<()> {
+        if window_mode == self.window_mode {
+            return Ok(());
+        }
+        if self.window_mode == WindowMode::Windowed {
+            self.windowed_position = self.window.get_pos();
+            self.windowed_size = self.get_window_size();
+        }
+        let window = &mut self.window;
+        match window_mode {
+            WindowMode::Windowed => window.set_monitor(
+                glfw::WindowMode::Windowed,
+                self.windowed_position.0,
+                self.windowed_position.1,
+                self.windowed_size.width,
+                self.windowed_size.height,
+                None,
+            ),
+            WindowMode::Fullscreen { monitor } => {
+                let fullscreen_size = self.fullscreen_size;
+                with_monitor(&mut self.glfw, monitor, |monitor| {
+                    window.set_monitor(
+                        glfw::WindowMode::FullScreen(monitor),
+                        0,
+                        0,
+                        fullscreen_size.width,
+                        fullscreen_size.height,
+                        None,
+                    );
+                    Ok(())
+                })?;
+            }
+            WindowMode::BorderlessFullscreen { monitor } => {
+                with_monitor(&mut self.glfw, monitor, |monitor| {
+                    // Matching the current video mode of the monitor keeps it from switching
+                    let video_mode = monitor.get_video_mode().context("Failed to get the video mode of the monitor.")?;
+                    window.set_monitor(
+                        glfw::WindowMode::FullScreen(monitor),
+                        0,
+                        0,
+                        video_mode.width,
+                        video_mode.height,
+                        Some(video_mode.refresh_rate),
+                    );
+                    Ok(())
+                })?;
+            }
+        }
+        self.window_mode = window_mode;
+        Ok(())
+    }
+
+    fn get_window_mode(&self) -> WindowMode {
+        self.window_mode
+    }
+}
+
+// Calls f with a connected monitor, the primary monitor is 0.
+fn with_monitor<F>(glfw: &mut glfw::Glfw, index: usize, f: F) -> anyhow::Result<()>
+where F: FnOnce(&glfw::Monitor) -> anyhow::Result<()> {
+    glfw.with_connected_monitors(|_, monitors| match monitors.get(index) {
+        Some(monitor) => f(monitor),
+        None => Err(anyhow::anyhow!("No monitor {}, {} connected.", index, monitors.len())),
+    })
+}
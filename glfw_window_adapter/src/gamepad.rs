@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+
+use common::input::{GamepadAxis, GamepadButton, InputEvent};
+
+// Below this the sticks and the triggers are considered at rest, worn sticks don't quite go back to the center.
+pub const DEFAULT_GAMEPAD_DEAD_ZONE: f32 = 0.15;
+
+const JOYSTICKS: [glfw::JoystickId; 16] = [
+    glfw::JoystickId::Joystick1,
+    glfw::JoystickId::Joystick2,
+    glfw::JoystickId::Joystick3,
+    glfw::JoystickId::Joystick4,
+    glfw::JoystickId::Joystick5,
+    glfw::JoystickId::Joystick6,
+    glfw::JoystickId::Joystick7,
+    glfw::JoystickId::Joystick8,
+    glfw::JoystickId::Joystick9,
+    glfw::JoystickId::Joystick10,
+    glfw::JoystickId::Joystick11,
+    glfw::JoystickId::Joystick12,
+    glfw::JoystickId::Joystick13,
+    glfw::JoystickId::Joystick14,
+    glfw::JoystickId::Joystick15,
+    glfw::JoystickId::Joystick16,
+];
+
+const BUTTONS: [(glfw::GamepadButton, GamepadButton); 15] = [
+    (glfw::GamepadButton::ButtonA, GamepadButton::A),
+    (glfw::GamepadButton::ButtonB, GamepadButton::B),
+    (glfw::GamepadButton::ButtonX, GamepadButton::X),
+    (glfw::GamepadButton::ButtonY, GamepadButton::Y),
+    (glfw::GamepadButton::ButtonLeftBumper, GamepadButton::LeftBumper),
+    (glfw::GamepadButton::ButtonRightBumper, GamepadButton::RightBumper),
+    (glfw::GamepadButton::ButtonBack, GamepadButton::Back),
+    (glfw::GamepadButton::ButtonStart, GamepadButton::Start),
+    (glfw::GamepadButton::ButtonGuide, GamepadButton::Guide),
+    (glfw::GamepadButton::ButtonLeftThumb, GamepadButton::LeftThumb),
+    (glfw::GamepadButton::ButtonRightThumb, GamepadButton::RightThumb),
+    (glfw::GamepadButton::ButtonDpadUp, GamepadButton::DpadUp),
+    (glfw::GamepadButton::ButtonDpadRight, GamepadButton::DpadRight),
+    (glfw::GamepadButton::ButtonDpadDown, GamepadButton::DpadDown),
+    (glfw::GamepadButton::ButtonDpadLeft, GamepadButton::DpadLeft),
+];
+
+// The x and y axes of each stick
+const STICKS: [[(glfw::GamepadAxis, GamepadAxis); 2]; 2] = [
+    [(glfw::GamepadAxis::AxisLeftX, GamepadAxis::LeftX), (glfw::GamepadAxis::AxisLeftY, GamepadAxis::LeftY)],
+    [(glfw::GamepadAxis::AxisRightX, GamepadAxis::RightX), (glfw::GamepadAxis::AxisRightY, GamepadAxis::RightY)],
+];
+
+const TRIGGERS: [(glfw::GamepadAxis, GamepadAxis); 2] = [
+    (glfw::GamepadAxis::AxisLeftTrigger, GamepadAxis::LeftTrigger),
+    (glfw::GamepadAxis::AxisRightTrigger, GamepadAxis::RightTrigger),
+];
+
+// What was reported for a gamepad by the last poll.
+struct PolledGamepad {
+    pressed_buttons: [bool; BUTTONS.len()],
+    axes: HashMap<GamepadAxis, f32>,
+}
+
+// GLFW has no events for the gamepads, their state is polled every frame and compared to the previous one.
+// Only the joysticks with a gamepad mapping are reported.
+pub struct GamepadPoller {
+    dead_zone: f32,
+    // By slot
+    gamepads: HashMap<u8, PolledGamepad>,
+}
+
+impl GamepadPoller {
+    pub fn new(dead_zone: f32) -> GamepadPoller {
+        GamepadPoller {
+            dead_zone,
+            gamepads: HashMap::new(),
+        }
+    }
+
+    pub fn set_dead_zone(&mut self, dead_zone: f32) {
+        self.dead_zone = dead_zone;
+    }
+
+    pub fn poll<F>(&mut self, glfw: &glfw::Glfw, mut handle_event: F) where F: FnMut(InputEvent) {
+        for (slot, id) in JOYSTICKS.into_iter().enumerate() {
+            let gamepad = slot as u8;
+            let joystick = glfw.get_joystick(id);
+            let state = if joystick.is_gamepad() { joystick.get_gamepad_state() } else { None };
+            let Some(state) = state else {
+                if self.gamepads.remove(&gamepad).is_some() {
+                    handle_event(InputEvent::GamepadDisconnected { gamepad });
+                }
+                continue;
+            };
+
+            let polled = self.gamepads.entry(gamepad).or_insert_with(|| {
+                handle_event(InputEvent::GamepadConnected {
+                    gamepad,
+                    name: joystick.get_gamepad_name().unwrap_or_default(),
+                });
+                PolledGamepad {
+                    pressed_buttons: [false; BUTTONS.len()],
+                    axes: HashMap::new(),
+                }
+            });
+
+            for (index, (glfw_button, button)) in BUTTONS.into_iter().enumerate() {
+                let pressed = state.get_button_state(glfw_button) == glfw::Action::Press;
+                if pressed != polled.pressed_buttons[index] {
+                    polled.pressed_buttons[index] = pressed;
+                    handle_event(if pressed {
+                        InputEvent::GamepadButtonPressed { gamepad, button }
+                    } else {
+                        InputEvent::GamepadButtonReleased { gamepad, button }
+                    });
+                }
+            }
+
+            let mut axes = Vec::with_capacity(6);
+            for [(glfw_x, x), (glfw_y, y)] in STICKS {
+                let (x_value, y_value) = stick_dead_zone(state.get_axis(glfw_x), state.get_axis(glfw_y), self.dead_zone);
+                axes.push((x, x_value));
+                axes.push((y, y_value));
+            }
+            for (glfw_trigger, trigger) in TRIGGERS {
+                // GLFW reports released triggers as -1
+                let value = (state.get_axis(glfw_trigger) + 1.0) / 2.0;
+                axes.push((trigger, trigger_dead_zone(value, self.dead_zone)));
+            }
+            for (axis, value) in axes {
+                if polled.axes.insert(axis, value) != Some(value) {
+                    handle_event(InputEvent::GamepadAxisMoved { gamepad, axis, value });
+                }
+            }
+        }
+    }
+}
+
+// Radial, so diagonals are not cut off, and rescaled so the values still start at 0 once out of the dead zone.
+fn stick_dead_zone(x: f32, y: f32, dead_zone: f32) -> (f32, f32) {
+    let magnitude = (x * x + y * y).sqrt();
+    if magnitude <= dead_zone {
+        return (0.0, 0.0);
+    }
+    let scale = ((magnitude - dead_zone) / (1.0 - dead_zone)).min(1.0) / magnitude;
+    (x * scale, y * scale)
+}
+
+fn trigger_dead_zone(value: f32, dead_zone: f32) -> f32 {
+    if value <= dead_zone {
+        return 0.0;
+    }
+    ((value - dead_zone) / (1.0 - dead_zone)).min(1.0)
+}
@@ -0,0 +1,124 @@
+use common::input::{InputEvent, Key, MouseButton};
+use common::Dimentions;
+
+// The window agnostic equivalent of a GLFW event, None for the events the game doesn't need.
+pub fn translate_event(event: &glfw::WindowEvent) -> Option<InputEvent> {
+    match *event {
+        glfw::WindowEvent::Key(key, _, glfw::Action::Press, _) => Some(InputEvent::KeyPressed(translate_key(key))),
+        glfw::WindowEvent::Key(key, _, glfw::Action::Release, _) => Some(InputEvent::KeyReleased(translate_key(key))),
+        // Held keys are tracked by InputState, the repeats are only useful for text, which comes as Char
+        glfw::WindowEvent::Key(_, _, glfw::Action::Repeat, _) => None,
+        glfw::WindowEvent::MouseButton(button, glfw::Action::Release, _) => {
+            Some(InputEvent::MouseButtonReleased(translate_mouse_button(button)))
+        }
+        glfw::WindowEvent::MouseButton(button, _, _) => Some(InputEvent::MouseButtonPressed(translate_mouse_button(button))),
+        glfw::WindowEvent::CursorPos(x, y) => Some(InputEvent::CursorMoved { x: x as f32, y: y as f32 }),
+        glfw::WindowEvent::Scroll(x, y) => Some(InputEvent::Scrolled { x: x as f32, y: y as f32 }),
+        glfw::WindowEvent::Char(character) => Some(InputEvent::Text(character)),
+        glfw::WindowEvent::Focus(focused) => Some(InputEvent::Focused(focused)),
+        glfw::WindowEvent::FramebufferSize(width, height) => Some(InputEvent::Resized(Dimentions {
+            width: width as u32,
+            height: height as u32,
+        })),
+        glfw::WindowEvent::Close => Some(InputEvent::CloseRequested),
+        _ => None,
+    }
+}
+
+fn translate_mouse_button(button: glfw::MouseButton) -> MouseButton {
+    match button {
+        glfw::MouseButtonLeft => MouseButton::Left,
+        glfw::MouseButtonRight => MouseButton::Right,
+        glfw::MouseButtonMiddle => MouseButton::Middle,
+        other => MouseButton::Other(other as u8 + 1),
+    }
+}
+
+fn translate_key(key: glfw::Key) -> Key {
+    match key {
+        glfw::Key::A => Key::A,
+        glfw::Key::B => Key::B,
+        glfw::Key::C => Key::C,
+        glfw::Key::D => Key::D,
+        glfw::Key::E => Key::E,
+        glfw::Key::F => Key::F,
+        glfw::Key::G => Key::G,
+        glfw::Key::H => Key::H,
+        glfw::Key::I => Key::I,
+        glfw::Key::J => Key::J,
+        glfw::Key::K => Key::K,
+        glfw::Key::L => Key::L,
+        glfw::Key::M => Key::M,
+        glfw::Key::N => Key::N,
+        glfw::Key::O => Key::O,
+        glfw::Key::P => Key::P,
+        glfw::Key::Q => Key::Q,
+        glfw::Key::R => Key::R,
+        glfw::Key::S => Key::S,
+        glfw::Key::T => Key::T,
+        glfw::Key::U => Key::U,
+        glfw::Key::V => Key::V,
+        glfw::Key::W => Key::W,
+        glfw::Key::X => Key::X,
+        glfw::Key::Y => Key::Y,
+        glfw::Key::Z => Key::Z,
+        glfw::Key::Num0 => Key::Num0,
+        glfw::Key::Num1 => Key::Num1,
+        glfw::Key::Num2 => Key::Num2,
+        glfw::Key::Num3 => Key::Num3,
+        glfw::Key::Num4 => Key::Num4,
+        glfw::Key::Num5 => Key::Num5,
+        glfw::Key::Num6 => Key::Num6,
+        glfw::Key::Num7 => Key::Num7,
+        glfw::Key::Num8 => Key::Num8,
+        glfw::Key::Num9 => Key::Num9,
+        glfw::Key::F1 => Key::F1,
+        glfw::Key::F2 => Key::F2,
+        glfw::Key::F3 => Key::F3,
+        glfw::Key::F4 => Key::F4,
+        glfw::Key::F5 => Key::F5,
+        glfw::Key::F6 => Key::F6,
+        glfw::Key::F7 => Key::F7,
+        glfw::Key::F8 => Key::F8,
+        glfw::Key::F9 => Key::F9,
+        glfw::Key::F10 => Key::F10,
+        glfw::Key::F11 => Key::F11,
+        glfw::Key::F12 => Key::F12,
+        glfw::Key::Up => Key::Up,
+        glfw::Key::Down => Key::Down,
+        glfw::Key::Left => Key::Left,
+        glfw::Key::Right => Key::Right,
+        glfw::Key::Space => Key::Space,
+        glfw::Key::Enter => Key::Enter,
+        glfw::Key::Escape => Key::Escape,
+        glfw::Key::Tab => Key::Tab,
+        glfw::Key::Backspace => Key::Backspace,
+        glfw::Key::Insert => Key::Insert,
+        glfw::Key::Delete => Key::Delete,
+        glfw::Key::Home => Key::Home,
+        glfw::Key::End => Key::End,
+        glfw::Key::PageUp => Key::PageUp,
+        glfw::Key::PageDown => Key::PageDown,
+        glfw::Key::CapsLock => Key::CapsLock,
+        glfw::Key::LeftShift => Key::LeftShift,
+        glfw::Key::RightShift => Key::RightShift,
+        glfw::Key::LeftControl => Key::LeftControl,
+        glfw::Key::RightControl => Key::RightControl,
+        glfw::Key::LeftAlt => Key::LeftAlt,
+        glfw::Key::RightAlt => Key::RightAlt,
+        glfw::Key::LeftSuper => Key::LeftSuper,
+        glfw::Key::RightSuper => Key::RightSuper,
+        glfw::Key::Minus => Key::Minus,
+        glfw::Key::Equal => Key::Equal,
+        glfw::Key::LeftBracket => Key::LeftBracket,
+        glfw::Key::RightBracket => Key::RightBracket,
+        glfw::Key::Backslash => Key::Backslash,
+        glfw::Key::Semicolon => Key::Semicolon,
+        glfw::Key::Apostrophe => Key::Apostrophe,
+        glfw::Key::Comma => Key::Comma,
+        glfw::Key::Period => Key::Period,
+        glfw::Key::Slash => Key::Slash,
+        glfw::Key::GraveAccent => Key::GraveAccent,
+        _ => Key::Unknown,
+    }
+}
@@ -1,2 +1,4 @@
 pub mod adapter;
-
+pub mod input;
+pub mod gamepad;
+pub mod window_handle;
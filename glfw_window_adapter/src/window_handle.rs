@@ -0,0 +1,39 @@
+use raw_window_handle::{
+    DisplayHandle, HandleError, HasDisplayHandle, HasWindowHandle, RawDisplayHandle, RawWindowHandle, WindowHandle,
+};
+
+// The raw handles of the window, given to the surface so the adapter keeps sole ownership of the window.
+// The only unsafe code of the adapter, the window is otherwise used through the safe glfw API.
+pub struct GLFWWindowHandle {
+    window: RawWindowHandle,
+    display: RawDisplayHandle,
+}
+
+impl GLFWWindowHandle {
+    pub fn new(window: &glfw::PWindow) -> anyhow::Result<GLFWWindowHandle> {
+        Ok(GLFWWindowHandle {
+            window: window.window_handle()?.as_raw(),
+            display: window.display_handle()?.as_raw(),
+        })
+    }
+}
+
+// SAFETY: the handles are identifiers of the window and its display, never dereferenced by this type.
+// The surface only uses them to be created and presented to, which GLFW and the graphics APIs allow from any thread.
+unsafe impl Send for GLFWWindowHandle {}
+unsafe impl Sync for GLFWWindowHandle {}
+
+impl HasWindowHandle for GLFWWindowHandle {
+    fn window_handle(&self) -> Result<WindowHandle<'_>, HandleError> {
+        // SAFETY: the handle was taken from a live window, which the adapter destroys only when it is dropped,
+        // and WindowAdapter::get_window_handle requires the surface to be dropped before the adapter.
+        Ok(unsafe { WindowHandle::borrow_raw(self.window) })
+    }
+}
+
+impl HasDisplayHandle for GLFWWindowHandle {
+    fn display_handle(&self) -> Result<DisplayHandle<'_>, HandleError> {
+        // SAFETY: the display connection is owned by GLFW, which stays initialized as long as the adapter.
+        Ok(unsafe { DisplayHandle::borrow_raw(self.display) })
+    }
+}
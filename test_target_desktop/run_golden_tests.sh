@@ -0,0 +1,53 @@
+#!/usr/bin/env bash
+# Renders every render-to-disk scenario and compares it to its reference image in test_references/.
+# Runs on Mesa's llvmpipe software rasterizer through the GL backend so it works on machines without a GPU,
+# the references were rendered on it and other drivers rasterize differently enough to fail the comparison.
+# Pass --update-references to regenerate the reference images instead, then review and commit them.
+set -euo pipefail
+
+cd "$(dirname "$0")"
+
+export WGPU_BACKEND="${WGPU_BACKEND:-gl}"
+export WGPU_FALLBACK_ADAPTER="${WGPU_FALLBACK_ADAPTER:-allow}"
+# Makes Mesa pick llvmpipe even when a GPU driver is installed
+export LIBGL_ALWAYS_SOFTWARE=1
+
+MODE="--compare"
+if [ "${1:-}" = "--update-references" ]; then
+    MODE="--update-references"
+fi
+
+TESTS=(
+    simple_cube
+    instanced_cube
+    instanced_cube_dynamic_update
+    instance_count_update
+    gltf_cube
+    msaa_cube
+    msaa_coverage
+    cube_1920x1080
+    cube_1001x333
+    transparent_background_cube
+    gradient_background_cube
+    skybox_faces_cube
+    skybox_hdr_cube
+    draw_order
+    model_lifecycle
+    scaled_cubes
+    tinted_cubes
+    orthographic_cubes
+    perspective_camera_update
+)
+
+cargo build --release
+FAILED=()
+for TEST in "${TESTS[@]}"; do
+    if ! cargo run --release --quiet -- --test-name "$TEST" "$MODE"; then
+        FAILED+=("$TEST")
+    fi
+done
+
+if [ ${#FAILED[@]} -ne 0 ]; then
+    echo "Failed: ${FAILED[*]}, diff heatmaps are in test_outputs/" >&2
+    exit 1
+fi
@@ -0,0 +1,26 @@
+use std::path::Path;
+
+use wgpu_graphical_adapter::background::Background;
+
+use crate::utils::{get_cube_instances_by_absolute_time, load_cube_for_default_pipeline, make_adapter_to_render_to_disk};
+
+pub fn run(output_path: &Path, file_name: &str, background: Background) -> anyhow::Result<()> {
+    let mut state = make_adapter_to_render_to_disk(
+        output_path.join(file_name).into_boxed_path()
+    )?;
+    state.set_background(background);
+    load_cube_for_default_pipeline(&mut state, get_cube_instances_by_absolute_time(0))?;
+    state.render().unwrap();
+    Ok(())
+}
+
+pub fn run_transparent(output_path: &Path) -> anyhow::Result<()> {
+    run(output_path, "transparent_background_cube.png", Background::Transparent)
+}
+
+pub fn run_gradient(output_path: &Path) -> anyhow::Result<()> {
+    run(output_path, "gradient_background_cube.png", Background::VerticalGradient {
+        top: wgpu::Color { r: 0.02, g: 0.05, b: 0.2, a: 1.0 },
+        bottom: wgpu::Color { r: 0.6, g: 0.4, b: 0.3, a: 1.0 },
+    })
+}
@@ -0,0 +1,48 @@
+use std::path::Path;
+
+use wgpu_graphical_adapter::camera::{OrthographicCamera, PerspectiveCamera};
+
+use crate::utils::{get_cube_instances_by_absolute_time, load_cube_for_default_pipeline, make_adapter_to_render_to_disk};
+
+// The instanced cubes seen from straight above, without perspective.
+pub fn run_orthographic(output_path: &Path) -> anyhow::Result<()> {
+    let mut state = make_adapter_to_render_to_disk(
+        output_path.join("orthographic_cubes.png").into_boxed_path()
+    )?;
+    load_cube_for_default_pipeline(&mut state, get_cube_instances_by_absolute_time(0))?;
+    state.set_camera(Box::new(OrthographicCamera {
+        eye: (0.0, 20.0, 0.0).into(),
+        target: (0.0, 0.0, 0.0).into(),
+        // Looking down, up can't be the y axis
+        up: -cgmath::Vector3::unit_z(),
+        height: 34.0,
+        aspect: 1.0,
+        znear: 0.1,
+        zfar: 100.0,
+    }));
+    state.render()?;
+    Ok(())
+}
+
+// Several changes to a perspective camera in a single frame, only the last ones must show.
+pub fn run_perspective_update(output_path: &Path) -> anyhow::Result<()> {
+    let mut state = make_adapter_to_render_to_disk(
+        output_path.join("perspective_camera_update.png").into_boxed_path()
+    )?;
+    load_cube_for_default_pipeline(&mut state, get_cube_instances_by_absolute_time(0))?;
+    state.set_camera(Box::new(PerspectiveCamera {
+        eye: (0.0, 1.0, 1.0).into(),
+        target: (0.0, 0.0, 0.0).into(),
+        up: cgmath::Vector3::unit_y(),
+        aspect: 1.0,
+        fovy: 90.0,
+        znear: 0.1,
+        zfar: 100.0,
+    }));
+    state.update_camera_eye((-20.0, 10.0, 20.0).into());
+    state.update_camera(&mut |camera| {
+        camera.look_at(camera.position(), (0.0, -2.0, 0.0).into(), camera.up());
+    });
+    state.render()?;
+    Ok(())
+}
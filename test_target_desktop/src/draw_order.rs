@@ -0,0 +1,39 @@
+use std::path::Path;
+
+use cgmath::Rotation3;
+
+use wgpu_graphical_adapter::instance::Instance;
+
+use crate::utils::{CUBE_MODEL_ID, DEFAULT_PIPELINE_ID, load_cube_for_default_pipeline, make_adapter_to_render_to_disk};
+
+// Checks that models are drawn in loading order until they are given an explicit order.
+pub fn run(output_path: &Path) -> anyhow::Result<()> {
+    let mut state = make_adapter_to_render_to_disk(
+        output_path.join("draw_order.png").into_boxed_path()
+    )?;
+    load_cube_for_default_pipeline(&mut state, vec![Instance::new(
+        cgmath::Vector3::new(-2.0, 0.0, 0.0),
+        cgmath::Quaternion::from_axis_angle(cgmath::Vector3::unit_z(), cgmath::Deg(0.0)),
+    )])?;
+    for model_id in ["cube_2", "cube_3"] {
+        state.load_model_sync(DEFAULT_PIPELINE_ID, model_id, "cube.obj", vec![Instance::new(
+            cgmath::Vector3::new(if model_id == "cube_2" { 0.0 } else { 2.0 }, 0.0, 0.0),
+            cgmath::Quaternion::from_axis_angle(cgmath::Vector3::unit_z(), cgmath::Deg(0.0)),
+        )])?;
+    }
+    expect_model_ids(&state.list_models(DEFAULT_PIPELINE_ID)?, &[CUBE_MODEL_ID, "cube_2", "cube_3"])?;
+
+    state.set_model_order(DEFAULT_PIPELINE_ID, CUBE_MODEL_ID, 1)?;
+    state.set_model_order(DEFAULT_PIPELINE_ID, "cube_3", -1)?;
+    expect_model_ids(&state.list_models(DEFAULT_PIPELINE_ID)?, &["cube_3", "cube_2", CUBE_MODEL_ID])?;
+
+    state.render()?;
+    Ok(())
+}
+
+fn expect_model_ids(actual: &[&str], expected: &[&str]) -> anyhow::Result<()> {
+    if actual != expected {
+        return Err(anyhow::anyhow!("Unexpected draw order: {:?}, expected {:?}", actual, expected));
+    }
+    Ok(())
+}
@@ -0,0 +1,32 @@
+use std::path::Path;
+use std::rc::Rc;
+
+use cgmath::Rotation3;
+
+use wgpu_graphical_adapter::instance::Instance;
+use wgpu_gltf_model_loader_adapter::GltfWgpuModelLoaderAdapter;
+
+use crate::utils::{CUBE_MODEL_ID, DEFAULT_PIPELINE_ID, DEFAULT_RENDER_TO_DISK_SIZE, make_adapter_to_render_to_disk_with_loader};
+
+pub fn run(output_path: &Path) -> anyhow::Result<()> {
+    let wgpu_gltf_model_loader_adapter = Rc::new(GltfWgpuModelLoaderAdapter::new(
+        Box::from(env!("OUT_DIR")),
+    ));
+    let mut state = make_adapter_to_render_to_disk_with_loader(
+        output_path.join("gltf_cube.png").into_boxed_path(),
+        wgpu_gltf_model_loader_adapter,
+        DEFAULT_RENDER_TO_DISK_SIZE,
+        1,
+    )?;
+    state.load_model_sync(
+        DEFAULT_PIPELINE_ID,
+        CUBE_MODEL_ID,
+        "cube.gltf",
+        vec![Instance::new(
+            cgmath::Vector3::new(0.0, 0.0, 0.0),
+            cgmath::Quaternion::from_axis_angle(cgmath::Vector3::unit_z(), cgmath::Deg(0.0)),
+        )],
+    )?;
+    state.render().unwrap();
+    Ok(())
+}
@@ -0,0 +1,163 @@
+use std::path::Path;
+
+use anyhow::Context;
+use image::{Rgba, RgbaImage};
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum Metric {
+    // Largest difference of the RGBA channels.
+    Absolute,
+    // Difference of brightness and chrominance in the YIQ color space, closer to what the eye notices.
+    // See https://github.com/mapbox/pixelmatch
+    Perceptual,
+}
+
+pub struct ComparisonOptions {
+    pub metric: Metric,
+    // Pixels differing by more than this, between 0 and 1, are counted as failing.
+    pub tolerance: f32,
+    // The ratio of failing pixels above which the comparison fails.
+    pub max_failing_pixels_ratio: f32,
+}
+
+// Compares the output to its reference, writing a heatmap of the differences next to the output
+// on failure. Returns whether the output matches.
+pub fn compare_to_reference(
+    output_path: &Path,
+    reference_path: &Path,
+    options: &ComparisonOptions,
+) -> anyhow::Result<bool> {
+    let output = image::open(output_path)
+        .with_context(|| format!("Failed to open output image: {:?}", output_path))?
+        .to_rgba8();
+    // Every scenario draws something, a blank output would match a blank reference
+    if is_single_color(&output) {
+        log::error!("{:?} is a single color, nothing was drawn", output_path);
+        return Ok(false);
+    }
+    if !reference_path.exists() {
+        log::error!(
+            "{:?} has no reference image {:?}, run with --update-references to create it",
+            output_path, reference_path,
+        );
+        return Ok(false);
+    }
+    let reference = image::open(reference_path)
+        .with_context(|| format!("Failed to open reference image: {:?}", reference_path))?
+        .to_rgba8();
+
+    if output.dimensions() != reference.dimensions() {
+        log::error!(
+            "{:?} is {:?} but its reference is {:?}",
+            output_path, output.dimensions(), reference.dimensions(),
+        );
+        return Ok(false);
+    }
+
+    let mut heatmap = RgbaImage::new(output.width(), output.height());
+    let mut failing_pixels: u64 = 0;
+    let mut max_difference: f32 = 0.0;
+    for ((output_pixel, reference_pixel), heatmap_pixel) in output.pixels()
+        .zip(reference.pixels())
+        .zip(heatmap.pixels_mut())
+    {
+        let difference = pixel_difference(output_pixel, reference_pixel, options.metric);
+        max_difference = max_difference.max(difference);
+        if difference > options.tolerance {
+            failing_pixels += 1;
+        }
+        *heatmap_pixel = heatmap_color(reference_pixel, difference, options.tolerance);
+    }
+
+    let total_pixels = output.width() as u64 * output.height() as u64;
+    let failing_pixels_ratio = failing_pixels as f32 / total_pixels as f32;
+    let matches = failing_pixels_ratio <= options.max_failing_pixels_ratio;
+    log::info!(
+        "Compared {:?} to {:?}: {} of {} pixels failing, max difference {}",
+        output_path, reference_path, failing_pixels, total_pixels, max_difference,
+    );
+
+    if !matches {
+        let heatmap_path = output_path.with_file_name(format!(
+            "{}_diff.png",
+            output_path.file_stem().unwrap_or_default().to_string_lossy(),
+        ));
+        heatmap.save(&heatmap_path)?;
+        log::error!(
+            "{:?} differs from its reference on {} pixels ({:.4}%), see {:?}",
+            output_path, failing_pixels, failing_pixels_ratio * 100.0, heatmap_path,
+        );
+    }
+    Ok(matches)
+}
+
+pub fn update_reference(output_path: &Path, reference_path: &Path) -> anyhow::Result<()> {
+    let output = image::open(output_path)
+        .with_context(|| format!("Failed to open output image: {:?}", output_path))?
+        .to_rgba8();
+    if is_single_color(&output) {
+        return Err(anyhow::anyhow!(
+            "{:?} is a single color, nothing was drawn, refusing to use it as a reference",
+            output_path,
+        ));
+    }
+    if let Some(parent) = reference_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::copy(output_path, reference_path)
+        .with_context(|| format!("Failed to copy {:?} to {:?}", output_path, reference_path))?;
+    log::info!("Updated reference image: {:?}", reference_path);
+    Ok(())
+}
+
+// Between 0 (identical) and 1 (black against white).
+fn pixel_difference(a: &Rgba<u8>, b: &Rgba<u8>, metric: Metric) -> f32 {
+    match metric {
+        Metric::Absolute => a.0.iter()
+            .zip(b.0.iter())
+            .map(|(a, b)| a.abs_diff(*b))
+            .max()
+            .unwrap_or(0) as f32 / 255.0,
+        Metric::Perceptual => {
+            let (y_a, i_a, q_a) = to_yiq(blend_on_white(a));
+            let (y_b, i_b, q_b) = to_yiq(blend_on_white(b));
+            let (y, i, q) = (y_a - y_b, i_a - i_b, q_a - q_b);
+            // The largest possible delta is 35215 for 0-255 channels
+            let delta = 0.5053 * y * y + 0.299 * i * i + 0.1957 * q * q;
+            (delta / 35215.0).sqrt().min(1.0)
+        }
+    }
+}
+
+fn blend_on_white(pixel: &Rgba<u8>) -> [f32; 3] {
+    let alpha = pixel.0[3] as f32 / 255.0;
+    [0, 1, 2].map(|channel| 255.0 + (pixel.0[channel] as f32 - 255.0) * alpha)
+}
+
+fn to_yiq([r, g, b]: [f32; 3]) -> (f32, f32, f32) {
+    (
+        r * 0.2988953 + g * 0.5866225 + b * 0.1144822,
+        r * 0.595978 - g * 0.2741761 - b * 0.3218019,
+        r * 0.2114702 - g * 0.5226171 + b * 0.3111469,
+    )
+}
+
+// Faded grayscale reference where the pixels match, yellow to red where they don't.
+fn heatmap_color(reference_pixel: &Rgba<u8>, difference: f32, tolerance: f32) -> Rgba<u8> {
+    if difference <= tolerance {
+        let [r, g, b] = blend_on_white(reference_pixel);
+        let luma = 0.299 * r + 0.587 * g + 0.114 * b;
+        let faded = (255.0 - (255.0 - luma) * 0.1) as u8;
+        return Rgba([faded, faded, faded, 255]);
+    }
+    let intensity = ((difference - tolerance) / (1.0 - tolerance).max(f32::EPSILON)).clamp(0.0, 1.0);
+    Rgba([255, (255.0 * (1.0 - intensity)) as u8, 0, 255])
+}
+
+fn is_single_color(image: &RgbaImage) -> bool {
+    let mut pixels = image.pixels();
+    match pixels.next() {
+        Some(first) => pixels.all(|pixel| pixel == first),
+        None => true,
+    }
+}
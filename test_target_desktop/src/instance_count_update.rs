@@ -0,0 +1,33 @@
+use std::path::Path;
+
+use cgmath::Rotation3;
+
+use wgpu_graphical_adapter::instance::Instance;
+
+use crate::utils::{CUBE_MODEL_ID, DEFAULT_PIPELINE_ID, get_cube_instances_by_absolute_time, load_cube_for_default_pipeline, make_adapter_to_render_to_disk};
+
+// Shrinks, then grows the instances past the initial buffer, and finally moves a single row in place.
+// Only the instances of the last update must be drawn.
+pub fn run(output_path: &Path) -> anyhow::Result<()> {
+    let mut state = make_adapter_to_render_to_disk(
+        output_path.join("instance_count_update.png").into_boxed_path()
+    )?;
+    let instances = get_cube_instances_by_absolute_time(0);
+    load_cube_for_default_pipeline(&mut state, instances[..10].to_vec())?;
+    state.update_model_instances(DEFAULT_PIPELINE_ID, CUBE_MODEL_ID, instances[..3].to_vec())?;
+    state.update_model_instances(DEFAULT_PIPELINE_ID, CUBE_MODEL_ID, instances[..50].to_vec())?;
+    let rotation = cgmath::Quaternion::from_axis_angle(cgmath::Vector3::unit_y(), cgmath::Deg(0.0));
+    let raised_row = instances[20..30].iter()
+        .map(|instance| {
+            let position = instance.model.w.truncate() + cgmath::Vector3::unit_y() * 3.0;
+            Instance::new(position, rotation)
+        })
+        .collect::<Vec<_>>();
+    state.update_model_instances_range(DEFAULT_PIPELINE_ID, CUBE_MODEL_ID, 20, raised_row)?;
+    // Past the 50 instances in use, even though the buffer is larger
+    if state.update_model_instances_range(DEFAULT_PIPELINE_ID, CUBE_MODEL_ID, 45, instances[..10].to_vec()).is_ok() {
+        return Err(anyhow::anyhow!("Out of range instances were accepted"));
+    }
+    state.render()?;
+    Ok(())
+}
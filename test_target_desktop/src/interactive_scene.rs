@@ -0,0 +1,111 @@
+use std::time::{Instant, SystemTime};
+
+use common::input::{InputEvent, InputState, Key};
+use common::window::{CursorMode, WindowMode};
+use common::Dimentions;
+use wgpu_graphical_adapter::camera_controller::{CameraController, CameraControllerInput, FirstPersonCameraController, FlyCameraController, OrbitCameraController};
+use wgpu_graphical_adapter::state::WgpuGraphicalAdapterState;
+
+use crate::utils::{CUBE_MODEL_ID, DEFAULT_PIPELINE_ID, WindowBackend, get_cube_instances_by_absolute_time, load_cube_for_default_pipeline, make_window_title};
+
+// The window changes asked by a frame, applied by the loop of each window backend.
+#[derive(Default)]
+pub struct WindowChanges {
+    pub title: Option<String>,
+    pub window_mode: Option<WindowMode>,
+    pub cursor_mode: Option<CursorMode>,
+}
+
+// The animated cubes of the interactive tests.
+// 1 orbits, 2 flies and 3 walks, the right mouse button looks around, F11 toggles fullscreen
+// and C locks the cursor to the window.
+pub struct InteractiveScene {
+    state: Box<dyn WgpuGraphicalAdapterState>,
+    window_backend: WindowBackend,
+    camera_controller: Box<dyn CameraController>,
+    cursor_locked: bool,
+    new_size: Option<Dimentions>,
+    time: SystemTime,
+    acc_time: u32,
+}
+
+impl InteractiveScene {
+    pub fn new(mut state: Box<dyn WgpuGraphicalAdapterState>, window_backend: WindowBackend) -> anyhow::Result<InteractiveScene> {
+        load_cube_for_default_pipeline(&mut state, get_cube_instances_by_absolute_time(0))?;
+        Ok(InteractiveScene {
+            state,
+            window_backend,
+            camera_controller: Box::new(OrbitCameraController::default()),
+            cursor_locked: false,
+            new_size: None,
+            time: SystemTime::now(),
+            acc_time: 0,
+        })
+    }
+
+    pub fn handle_event(&mut self, event: &InputEvent) {
+        log::info!("{:?}", event);
+        // Resized once per frame, there can be many events while the window is dragged
+        if let InputEvent::Resized(size) = event {
+            self.new_size = Some(*size);
+        }
+    }
+
+    pub fn frame(&mut self, input_state: &InputState, window_mode: WindowMode) -> anyhow::Result<WindowChanges> {
+        let delta_time = self.time.elapsed().unwrap();
+        self.acc_time += delta_time.as_millis() as u32;
+        self.time = SystemTime::now();
+        if let Some(size) = self.new_size.take() {
+            self.state.resize(size)?;
+        }
+
+        let mut changes = WindowChanges::default();
+        let mut controller_name = None;
+        if input_state.is_key_just_pressed(Key::Num1) {
+            self.camera_controller = Box::new(OrbitCameraController::default());
+            controller_name = Some("Orbit");
+        } else if input_state.is_key_just_pressed(Key::Num2) {
+            self.camera_controller = Box::new(FlyCameraController::default());
+            controller_name = Some("Fly");
+        } else if input_state.is_key_just_pressed(Key::Num3) {
+            self.camera_controller = Box::new(FirstPersonCameraController::default());
+            controller_name = Some("First Person");
+        }
+        if let Some(controller_name) = controller_name {
+            changes.title = Some(make_window_title(self.window_backend, &format!("Simple Cube - {}", controller_name)));
+        }
+        if input_state.is_key_just_pressed(Key::F11) {
+            changes.window_mode = Some(match window_mode {
+                WindowMode::Windowed => WindowMode::BorderlessFullscreen { monitor: 0 },
+                _ => WindowMode::Windowed,
+            });
+        }
+        if input_state.is_key_just_pressed(Key::C) {
+            self.cursor_locked = !self.cursor_locked;
+            changes.cursor_mode = Some(if self.cursor_locked { CursorMode::Disabled } else { CursorMode::Normal });
+        }
+
+        let mut input = CameraControllerInput::from_input_state(input_state);
+        // A locked cursor always looks around
+        input.look |= self.cursor_locked;
+        let camera_controller = &mut self.camera_controller;
+        self.state.update_camera(&mut |camera| camera_controller.update(camera, &input, delta_time));
+
+        self.state.update_model_instances(
+            DEFAULT_PIPELINE_ID,
+            CUBE_MODEL_ID,
+            get_cube_instances_by_absolute_time(self.acc_time),
+        )?;
+
+        let start = Instant::now();
+        self.state.render()?;
+        let elapsed = start.elapsed();
+        log::info!(
+            target: "performance",
+            time_unit = "microseconds",
+            frame_time = elapsed.as_micros();
+            "",
+        );
+        Ok(changes)
+    }
+}
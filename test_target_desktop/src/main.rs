@@ -3,8 +3,22 @@ use clap::Parser;
 mod simple_cube;
 mod instanced_cube;
 mod instanced_cube_dynamic_update;
+mod instance_count_update;
+mod gltf_cube;
+mod msaa_cube;
+mod unaligned_resolution_cube;
+mod background_cube;
+mod skybox_cube;
+mod draw_order;
+mod model_lifecycle;
+mod scaled_cubes;
+mod tinted_cubes;
+mod camera_control;
 mod utils;
-mod test_glfw_adapter;
+mod interactive_scene;
+mod test_window_adapter;
+mod test_winit_adapter;
+mod golden;
 
 #[derive(Parser, Debug)]
 #[command(name = "banshee_wgpu_adapter_test")]
@@ -13,6 +27,32 @@ mod test_glfw_adapter;
 struct Args {
     #[arg(short, long)]
     test_name: String,
+
+    /// Compares the images written by the test to their reference images and exits with 1 when they differ.
+    #[arg(long)]
+    compare: bool,
+
+    /// Overwrites the reference images with the images written by the test.
+    #[arg(long, conflicts_with = "compare")]
+    update_references: bool,
+
+    /// The window library of the interactive tests.
+    #[arg(long, value_enum, default_value_t = utils::WindowBackend::Glfw)]
+    window_backend: utils::WindowBackend,
+
+    #[arg(long, default_value = "test_references")]
+    reference_folder: std::path::PathBuf,
+
+    #[arg(long, value_enum, default_value_t = golden::Metric::Perceptual)]
+    metric: golden::Metric,
+
+    /// Per pixel difference, between 0 and 1, above which a pixel fails.
+    #[arg(long, default_value_t = 0.05)]
+    tolerance: f32,
+
+    /// Ratio of failing pixels above which the comparison fails.
+    #[arg(long, default_value_t = 0.001)]
+    max_failing_pixels_ratio: f32,
 }
 
 
@@ -25,21 +65,136 @@ fn main() {
         std::fs::create_dir(output_folder).unwrap();
     }
     let args = Args::parse();
-    match args.test_name {
-        test_name if test_name == "simple_cube" => {
+    // The images written by each test, checked against their reference in compare mode
+    let outputs: Vec<&str> = match args.test_name.as_str() {
+        "simple_cube" => {
             simple_cube::run(output_folder).unwrap();
+            vec!["simple_cube.png"]
         },
-        test_name if test_name == "instanced_cube" => {
+        "instanced_cube" => {
             instanced_cube::run(output_folder).unwrap();
+            vec!["instanced_cube.png"]
         },
-        test_name if test_name == "instanced_cube_dynamic_update" => {
+        "instanced_cube_dynamic_update" => {
             instanced_cube_dynamic_update::run(output_folder).unwrap();
+            vec!["instanced_cube_update.png"]
+        },
+        "instance_count_update" => {
+            instance_count_update::run(output_folder).unwrap();
+            vec!["instance_count_update.png"]
+        },
+        "gltf_cube" => {
+            gltf_cube::run(output_folder).unwrap();
+            vec!["gltf_cube.png"]
+        },
+        "msaa_cube" => {
+            msaa_cube::run(output_folder).unwrap();
+            vec!["msaa_cube.png"]
+        },
+        // Checks itself, the images are only written to look at
+        "msaa_coverage" => {
+            msaa_cube::run_coverage(output_folder).unwrap();
+            vec![]
+        },
+        "cube_1920x1080" => {
+            unaligned_resolution_cube::run(output_folder, 1920, 1080).unwrap();
+            vec!["cube_1920x1080.png"]
+        },
+        "cube_1001x333" => {
+            unaligned_resolution_cube::run(output_folder, 1001, 333).unwrap();
+            vec!["cube_1001x333.png"]
+        },
+        "transparent_background_cube" => {
+            background_cube::run_transparent(output_folder).unwrap();
+            vec!["transparent_background_cube.png"]
+        },
+        "gradient_background_cube" => {
+            background_cube::run_gradient(output_folder).unwrap();
+            vec!["gradient_background_cube.png"]
         },
-        test_name if test_name == "glfw_adapter" => {
-            test_glfw_adapter::run().unwrap();
+        "skybox_faces_cube" => {
+            skybox_cube::run_faces(output_folder).unwrap();
+            vec!["skybox_faces_cube.png"]
+        },
+        "skybox_hdr_cube" => {
+            skybox_cube::run_equirectangular(output_folder).unwrap();
+            vec!["skybox_hdr_cube.png"]
+        },
+        "draw_order" => {
+            draw_order::run(output_folder).unwrap();
+            vec!["draw_order.png"]
+        },
+        "model_lifecycle" => {
+            model_lifecycle::run(output_folder).unwrap();
+            vec!["model_lifecycle.png"]
+        },
+        "scaled_cubes" => {
+            scaled_cubes::run(output_folder).unwrap();
+            vec!["scaled_cubes.png"]
+        },
+        "tinted_cubes" => {
+            tinted_cubes::run(output_folder).unwrap();
+            vec!["tinted_cubes.png"]
+        },
+        "orthographic_cubes" => {
+            camera_control::run_orthographic(output_folder).unwrap();
+            vec!["orthographic_cubes.png"]
+        },
+        "perspective_camera_update" => {
+            camera_control::run_perspective_update(output_folder).unwrap();
+            vec!["perspective_camera_update.png"]
+        },
+        // glfw_adapter is the name from before the winit backend
+        "window_adapter" | "glfw_adapter" => {
+            match args.window_backend {
+                utils::WindowBackend::Glfw | utils::WindowBackend::Winit => {
+                    test_window_adapter::run(args.window_backend).unwrap()
+                }
+                utils::WindowBackend::WinitEventLoop => test_winit_adapter::run_event_loop().unwrap(),
+            }
+            vec![]
         },
         _ => {
             panic!("Unknown test name: {}", args.test_name);
         }
+    };
+
+    if args.update_references {
+        let mut all_updated = true;
+        for output in &outputs {
+            if let Err(error) = golden::update_reference(&output_folder.join(output), &args.reference_folder.join(output)) {
+                log::error!("Failed to update the reference of {:?}: {:?}", output, error);
+                all_updated = false;
+            }
+        }
+        if !all_updated {
+            std::process::exit(1);
+        }
+    }
+
+    if args.compare {
+        let options = golden::ComparisonOptions {
+            metric: args.metric,
+            tolerance: args.tolerance,
+            max_failing_pixels_ratio: args.max_failing_pixels_ratio,
+        };
+        let mut all_match = true;
+        for output in &outputs {
+            // An image that can't be compared fails the test like a mismatch does
+            all_match &= match golden::compare_to_reference(
+                &output_folder.join(output),
+                &args.reference_folder.join(output),
+                &options,
+            ) {
+                Ok(matches) => matches,
+                Err(error) => {
+                    log::error!("Failed to compare {:?} to its reference: {:?}", output, error);
+                    false
+                }
+            };
+        }
+        if !all_match {
+            std::process::exit(1);
+        }
     }
 }
@@ -0,0 +1,46 @@
+use std::path::Path;
+
+use wgpu_graphical_adapter::errors::WgpuGraphicalAdapterError;
+
+use crate::utils::{CUBE_MODEL_ID, DEFAULT_PIPELINE_ID, get_cube_instances_by_absolute_time, load_cube_for_default_pipeline, make_adapter_to_render_to_disk};
+
+// Loads, unloads and reloads the cube, checking the errors returned for unknown and duplicate ids.
+// The output must look like instanced_cube.png.
+pub fn run(output_path: &Path) -> anyhow::Result<()> {
+    let mut state = make_adapter_to_render_to_disk(
+        output_path.join("model_lifecycle.png").into_boxed_path()
+    )?;
+    load_cube_for_default_pipeline(&mut state, get_cube_instances_by_absolute_time(0))?;
+    expect_error(
+        load_cube_for_default_pipeline(&mut state, get_cube_instances_by_absolute_time(0)),
+        |error| matches!(error, WgpuGraphicalAdapterError::ModelAlreadyLoaded(_)),
+    )?;
+
+    state.unload_model(DEFAULT_PIPELINE_ID, CUBE_MODEL_ID)?;
+    if state.has_model(DEFAULT_PIPELINE_ID, CUBE_MODEL_ID)? || !state.list_models(DEFAULT_PIPELINE_ID)?.is_empty() {
+        return Err(anyhow::anyhow!("The cube is still loaded after being unloaded"));
+    }
+    expect_error(
+        state.unload_model(DEFAULT_PIPELINE_ID, CUBE_MODEL_ID),
+        |error| matches!(error, WgpuGraphicalAdapterError::ModelNotFound(_)),
+    )?;
+    expect_error(
+        state.has_model("unknown", CUBE_MODEL_ID).map(|_| ()),
+        |error| matches!(error, WgpuGraphicalAdapterError::PipelineNotFound(_)),
+    )?;
+
+    load_cube_for_default_pipeline(&mut state, get_cube_instances_by_absolute_time(0))?;
+    state.render()?;
+    Ok(())
+}
+
+fn expect_error(
+    result: anyhow::Result<()>,
+    is_expected: impl Fn(&WgpuGraphicalAdapterError) -> bool,
+) -> anyhow::Result<()> {
+    match result {
+        Err(error) if error.downcast_ref::<WgpuGraphicalAdapterError>().is_some_and(&is_expected) => Ok(()),
+        Err(error) => Err(anyhow::anyhow!("Unexpected error: {}", error)),
+        Ok(()) => Err(anyhow::anyhow!("Expected an error")),
+    }
+}
@@ -0,0 +1,50 @@
+use std::path::Path;
+
+use wgpu_graphical_adapter::background::Background;
+
+use crate::utils::{DEFAULT_RENDER_TO_DISK_SIZE, get_cube_instances_by_absolute_time, load_cube_for_default_pipeline, make_adapter_to_render_to_disk_with_loader, make_obj_loader};
+
+pub fn run(output_path: &Path) -> anyhow::Result<()> {
+    let mut state = make_adapter_to_render_to_disk_with_loader(
+        output_path.join("msaa_cube.png").into_boxed_path(),
+        make_obj_loader(),
+        DEFAULT_RENDER_TO_DISK_SIZE,
+        4,
+    )?;
+    load_cube_for_default_pipeline(&mut state, get_cube_instances_by_absolute_time(0))?;
+    state.render().unwrap();
+    Ok(())
+}
+
+// Renders the cubes over a transparent background with and without multisampling, the cubes must cover
+// the same pixels. Multisampling only adds the partially covered pixels of the edges.
+pub fn run_coverage(output_path: &Path) -> anyhow::Result<()> {
+    let single_sampled = render_transparent(&output_path.join("msaa_coverage_1x.png"), 1)?;
+    let multisampled = render_transparent(&output_path.join("msaa_coverage_4x.png"), 4)?;
+    if single_sampled == 0 {
+        return Err(anyhow::anyhow!("The cubes cover no pixel without multisampling"));
+    }
+    let difference = single_sampled.abs_diff(multisampled) as f32 / single_sampled as f32;
+    if difference > 0.05 {
+        return Err(anyhow::anyhow!(
+            "The cubes cover {} pixels with 4 samples but {} with 1 sample",
+            multisampled, single_sampled,
+        ));
+    }
+    Ok(())
+}
+
+// Returns the number of pixels the cubes cover.
+fn render_transparent(output_path: &Path, sample_count: u32) -> anyhow::Result<usize> {
+    let mut state = make_adapter_to_render_to_disk_with_loader(
+        output_path.to_path_buf().into_boxed_path(),
+        make_obj_loader(),
+        DEFAULT_RENDER_TO_DISK_SIZE,
+        sample_count,
+    )?;
+    state.set_background(Background::Transparent);
+    load_cube_for_default_pipeline(&mut state, get_cube_instances_by_absolute_time(0))?;
+    state.render()?;
+    let image = image::open(output_path)?.to_rgba8();
+    Ok(image.pixels().filter(|pixel| pixel[3] > 0).count())
+}
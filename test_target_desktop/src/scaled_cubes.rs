@@ -0,0 +1,30 @@
+use std::path::Path;
+
+use cgmath::Rotation3;
+
+use wgpu_graphical_adapter::instance::Instance;
+
+use crate::utils::{load_cube_for_default_pipeline, make_adapter_to_render_to_disk};
+
+// Uniformly, non-uniformly scaled and sheared cubes, their lighting must stay consistent with the unscaled one.
+pub fn run(output_path: &Path) -> anyhow::Result<()> {
+    let mut state = make_adapter_to_render_to_disk(
+        output_path.join("scaled_cubes.png").into_boxed_path()
+    )?;
+    let rotation = cgmath::Quaternion::from_axis_angle(cgmath::Vector3::unit_y(), cgmath::Deg(30.0));
+    #[rustfmt::skip]
+    let shear = cgmath::Matrix4::new(
+        1.0, 0.0, 0.0, 0.0,
+        0.6, 1.0, 0.0, 0.0,
+        0.0, 0.0, 1.0, 0.0,
+        6.0, 0.0, 0.0, 1.0,
+    );
+    load_cube_for_default_pipeline(&mut state, vec![
+        Instance::new(cgmath::Vector3::new(-6.0, 0.0, 0.0), rotation),
+        Instance::new_uniformly_scaled(cgmath::Vector3::new(-2.0, 0.0, 0.0), rotation, 0.5),
+        Instance::new_scaled(cgmath::Vector3::new(2.0, 0.0, 0.0), rotation, cgmath::Vector3::new(0.5, 2.0, 1.0)),
+        Instance::from_matrix(shear),
+    ])?;
+    state.render()?;
+    Ok(())
+}
@@ -0,0 +1,46 @@
+use std::path::Path;
+use std::rc::Rc;
+
+use wgpu_graphical_adapter::default_pipeline_impl::default_pipeline::DefaultWgpuGraphicalAdapterPipelineFactory;
+use wgpu_graphical_adapter::pipeline::PipelineRegistration;
+use wgpu_graphical_adapter::skybox_pipeline_impl::skybox_pipeline::SkyboxWgpuGraphicalAdapterPipelineFactory;
+use wgpu_obj_model_loader_adapter::ObjWgpuModelLoaderAdapter;
+
+use crate::utils::{DEFAULT_PIPELINE_ID, DEFAULT_PIPELINE_ORDER, DEFAULT_RENDER_TO_DISK_SIZE, SKYBOX_MODEL_ID, SKYBOX_PIPELINE_ID, SKYBOX_PIPELINE_ORDER, get_cube_instances_by_absolute_time, load_cube_for_default_pipeline, make_adapter_to_render_to_disk_with_pipelines};
+
+// The environment is either six face images ("skybox/{}.png") or an equirectangular HDR image ("sky.hdr").
+pub fn run(output_path: &Path, file_name: &str, environment: &str) -> anyhow::Result<()> {
+    let wgpu_obj_model_loader_adapter = Rc::new(ObjWgpuModelLoaderAdapter::new(
+        Box::from(env!("OUT_DIR")),
+    ));
+    let pipelines = vec![
+        PipelineRegistration::new(
+            DEFAULT_PIPELINE_ID,
+            DEFAULT_PIPELINE_ORDER,
+            Box::new(DefaultWgpuGraphicalAdapterPipelineFactory::new(wgpu_obj_model_loader_adapter.clone())),
+        ),
+        PipelineRegistration::new(
+            SKYBOX_PIPELINE_ID,
+            SKYBOX_PIPELINE_ORDER,
+            Box::new(SkyboxWgpuGraphicalAdapterPipelineFactory::new(wgpu_obj_model_loader_adapter)),
+        ),
+    ];
+    let mut state = make_adapter_to_render_to_disk_with_pipelines(
+        output_path.join(file_name).into_boxed_path(),
+        pipelines,
+        DEFAULT_RENDER_TO_DISK_SIZE,
+        1,
+    )?;
+    state.load_model_sync(SKYBOX_PIPELINE_ID, SKYBOX_MODEL_ID, environment, vec![])?;
+    load_cube_for_default_pipeline(&mut state, get_cube_instances_by_absolute_time(0))?;
+    state.render().unwrap();
+    Ok(())
+}
+
+pub fn run_faces(output_path: &Path) -> anyhow::Result<()> {
+    run(output_path, "skybox_faces_cube.png", "skybox/{}.png")
+}
+
+pub fn run_equirectangular(output_path: &Path) -> anyhow::Result<()> {
+    run(output_path, "skybox_hdr_cube.png", "sky.hdr")
+}
@@ -0,0 +1,25 @@
+use crate::interactive_scene::InteractiveScene;
+use crate::utils::{WindowBackend, make_adapter_with_window, make_window_adapter};
+
+// The game owns the loop, the same for every window backend with an adapter.
+pub fn run(window_backend: WindowBackend) -> anyhow::Result<()> {
+    let mut window_adapter = make_window_adapter(window_backend, "Simple Cube")?;
+    let mut scene = InteractiveScene::new(make_adapter_with_window(window_adapter.as_ref())?, window_backend)?;
+
+    while window_adapter.should_loop_continue() {
+        window_adapter.poll_events(&mut |event| scene.handle_event(event));
+
+        let changes = scene.frame(window_adapter.get_input_state(), window_adapter.get_window_mode())?;
+        if let Some(title) = changes.title {
+            window_adapter.set_title(&title)?;
+        }
+        if let Some(window_mode) = changes.window_mode {
+            window_adapter.set_window_mode(window_mode)?;
+        }
+        if let Some(cursor_mode) = changes.cursor_mode {
+            window_adapter.set_cursor_mode(cursor_mode)?;
+        }
+    }
+
+    Ok(())
+}
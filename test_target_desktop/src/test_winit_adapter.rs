@@ -0,0 +1,48 @@
+use common::input::{InputEvent, InputState};
+use winit_window_adapter::application::WinitApplication;
+use winit_window_adapter::window::WinitWindow;
+
+use crate::interactive_scene::{InteractiveScene, WindowChanges};
+use crate::utils::{WindowBackend, make_adapter_with_surface, make_window_config};
+
+// The event loop owns the loop and calls the scene back.
+pub fn run_event_loop() -> anyhow::Result<()> {
+    winit_window_adapter::application::run(&make_window_config(WindowBackend::WinitEventLoop, "Simple Cube"), EventLoopScene { scene: None })
+}
+
+struct EventLoopScene {
+    // Created with the window
+    scene: Option<InteractiveScene>,
+}
+
+impl WinitApplication for EventLoopScene {
+    fn init(&mut self, window: &mut WinitWindow) -> anyhow::Result<()> {
+        let state = make_adapter_with_surface(window.get_window().into(), window.get_framebuffer_size())?;
+        self.scene = Some(InteractiveScene::new(state, WindowBackend::WinitEventLoop)?);
+        Ok(())
+    }
+
+    fn handle_event(&mut self, event: &InputEvent) -> anyhow::Result<()> {
+        if let Some(scene) = &mut self.scene {
+            scene.handle_event(event);
+        }
+        Ok(())
+    }
+
+    fn frame(&mut self, window: &mut WinitWindow, input_state: &InputState) -> anyhow::Result<bool> {
+        let Some(scene) = &mut self.scene else {
+            return Ok(true);
+        };
+        let WindowChanges { title, window_mode, cursor_mode } = scene.frame(input_state, window.get_window_mode())?;
+        if let Some(title) = title {
+            window.set_title(&title)?;
+        }
+        if let Some(window_mode) = window_mode {
+            window.set_window_mode(window_mode)?;
+        }
+        if let Some(cursor_mode) = cursor_mode {
+            window.set_cursor_mode(cursor_mode)?;
+        }
+        Ok(true)
+    }
+}
@@ -0,0 +1,25 @@
+use std::path::Path;
+
+use cgmath::Rotation3;
+
+use wgpu_graphical_adapter::instance::Instance;
+
+use crate::utils::{load_cube_for_default_pipeline, make_adapter_to_render_to_disk};
+
+// The same model drawn untinted, tinted red, green and blue, and glowing.
+pub fn run(output_path: &Path) -> anyhow::Result<()> {
+    let mut state = make_adapter_to_render_to_disk(
+        output_path.join("tinted_cubes.png").into_boxed_path()
+    )?;
+    let rotation = cgmath::Quaternion::from_axis_angle(cgmath::Vector3::unit_y(), cgmath::Deg(30.0));
+    let at = |x: f32| Instance::new(cgmath::Vector3::new(x, 0.0, 0.0), rotation);
+    load_cube_for_default_pipeline(&mut state, vec![
+        at(-6.0).with_id(1),
+        at(-3.0).with_color([1.0, 0.2, 0.2, 1.0]).with_id(2),
+        at(0.0).with_color([0.2, 1.0, 0.2, 1.0]).with_id(3),
+        at(3.0).with_color([0.2, 0.2, 1.0, 1.0]).with_id(4),
+        at(6.0).with_emissive([0.8, 0.6, 0.0]).with_id(5),
+    ])?;
+    state.render()?;
+    Ok(())
+}
@@ -0,0 +1,19 @@
+use std::path::Path;
+
+use crate::utils::{get_cube_instances_by_absolute_time, load_cube_for_default_pipeline, make_adapter_to_render_to_disk_with_loader, make_obj_loader};
+
+// Rows of these widths are not multiples of 256 bytes and must be padded when copied out of the GPU.
+pub fn run(output_path: &Path, width: u32, height: u32) -> anyhow::Result<()> {
+    let mut state = make_adapter_to_render_to_disk_with_loader(
+        output_path.join(format!("cube_{}x{}.png", width, height)).into_boxed_path(),
+        make_obj_loader(),
+        common::Dimentions {
+            width,
+            height,
+        },
+        1,
+    )?;
+    load_cube_for_default_pipeline(&mut state, get_cube_instances_by_absolute_time(0))?;
+    state.render().unwrap();
+    Ok(())
+}
@@ -1,4 +1,3 @@
-use std::collections::HashMap;
 use std::path::Path;
 use std::rc::Rc;
 
@@ -6,58 +5,157 @@ use cgmath::{InnerSpace, Zero};
 use cgmath::prelude::*;
 use pollster::FutureExt;
 
+use common::window::WindowConfig;
+use common::window_adapter::WindowAdapter;
 use glfw_window_adapter::adapter::GLFWAdapter;
+use wgpu_graphical_adapter::camera::{Camera, PerspectiveCamera};
+use wgpu_graphical_adapter::config::RendererConfig;
 use wgpu_graphical_adapter::default_pipeline_impl::default_pipeline::DefaultWgpuGraphicalAdapterPipelineFactory;
+use wgpu_graphical_adapter::gateways::WgpuModelLoaderGateway;
 use wgpu_graphical_adapter::instance::Instance;
-use wgpu_graphical_adapter::pipeline::WgpuGraphicalAdapterPipelineFactory;
+use wgpu_graphical_adapter::pipeline::PipelineRegistration;
 use wgpu_graphical_adapter::state::{WgpuGraphicalAdapterState, WgpuGraphicalAdapterStateRenderToDisk, WgpuGraphicalAdapterStateWithWindow};
 use wgpu_obj_model_loader_adapter::ObjWgpuModelLoaderAdapter;
+use winit_window_adapter::adapter::WinitAdapter;
 
 pub static DEFAULT_PIPELINE_ID: &'static str = "default";
 pub static CUBE_MODEL_ID: &'static str = "cube_1";
+pub static SKYBOX_PIPELINE_ID: &'static str = "skybox";
+pub static SKYBOX_MODEL_ID: &'static str = "environment";
+pub const DEFAULT_PIPELINE_ORDER: i32 = 0;
+// After the opaque models, so only the pixels they don't cover are shaded
+pub const SKYBOX_PIPELINE_ORDER: i32 = 100;
+pub const WINDOW_SAMPLE_COUNT: u32 = 4;
+pub const DEFAULT_RENDER_TO_DISK_SIZE: common::Dimentions = common::Dimentions {
+    width: 512,
+    height: 512,
+};
 
-pub fn make_glfw_adapter(test_name: &str) -> anyhow::Result<GLFWAdapter> {
-    let title = format!("Banshee Engine v0.0.0 - GLFW/WGPU - Desktop Target - {}", test_name);
-    GLFWAdapter::new(title.as_str())
+// The window library used by the interactive tests
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WindowBackend {
+    Glfw,
+    // The game loop pumps the winit event loop, like with GLFW
+    Winit,
+    // The winit event loop runs the game loop
+    WinitEventLoop,
 }
 
-pub fn make_adapter_with_glfw_window(
-    glfw_adapter: &GLFWAdapter
+pub fn make_window_title(window_backend: WindowBackend, test_name: &str) -> String {
+    let window_library = match window_backend {
+        WindowBackend::Glfw => "GLFW",
+        WindowBackend::Winit | WindowBackend::WinitEventLoop => "winit",
+    };
+    format!("Banshee Engine v0.0.0 - {}/WGPU - Desktop Target - {}", window_library, test_name)
+}
+
+pub fn make_window_config(window_backend: WindowBackend, test_name: &str) -> WindowConfig {
+    WindowConfig {
+        title: make_window_title(window_backend, test_name),
+        min_size: Some(common::Dimentions {
+            width: 320,
+            height: 240,
+        }),
+        ..WindowConfig::default()
+    }
+}
+
+// The winit event loop runs the game loop, it has no window adapter
+pub fn make_window_adapter(window_backend: WindowBackend, test_name: &str) -> anyhow::Result<Box<dyn WindowAdapter>> {
+    let config = make_window_config(window_backend, test_name);
+    match window_backend {
+        WindowBackend::Glfw => Ok(Box::new(GLFWAdapter::new(&config)?)),
+        WindowBackend::Winit => Ok(Box::new(WinitAdapter::new(&config)?)),
+        WindowBackend::WinitEventLoop => Err(anyhow::anyhow!("The winit event loop backend has no window adapter.")),
+    }
+}
+
+pub fn make_adapter_with_window(
+    window_adapter: &dyn WindowAdapter
+) -> anyhow::Result<Box<dyn WgpuGraphicalAdapterState>> {
+    Ok(Box::new(WgpuGraphicalAdapterStateWithWindow::from_window_adapter(
+        window_adapter,
+        WINDOW_SAMPLE_COUNT,
+        &RendererConfig::from_env(),
+        make_default_camera(),
+        make_window_pipelines(),
+    ).block_on()?))
+}
+
+// For the windows without an adapter, the size is the framebuffer size of the window
+pub fn make_adapter_with_surface(
+    window: wgpu::SurfaceTarget<'static>,
+    size: common::Dimentions,
 ) -> anyhow::Result<Box<dyn WgpuGraphicalAdapterState>> {
-    let wgpu_obj_model_loader_adapter = Rc::new(ObjWgpuModelLoaderAdapter::new(
-        Box::from(env!("OUT_DIR")),
-    ));
-    let mut factories: HashMap<String, Box<dyn WgpuGraphicalAdapterPipelineFactory>> = HashMap::new();
-    factories.insert(
-        DEFAULT_PIPELINE_ID.to_string(),
-        Box::new(DefaultWgpuGraphicalAdapterPipelineFactory::new(wgpu_obj_model_loader_adapter)),
-    );
     Ok(Box::new(WgpuGraphicalAdapterStateWithWindow::new(
-        glfw_adapter.get_window().into(),
-        glfw_adapter.get_window_size(),
-        factories,
+        window,
+        size,
+        WINDOW_SAMPLE_COUNT,
+        &RendererConfig::from_env(),
+        make_default_camera(),
+        make_window_pipelines(),
     ).block_on()?))
 }
 
+fn make_window_pipelines() -> Vec<PipelineRegistration> {
+    vec![PipelineRegistration::new(
+        DEFAULT_PIPELINE_ID,
+        DEFAULT_PIPELINE_ORDER,
+        Box::new(DefaultWgpuGraphicalAdapterPipelineFactory::new(make_obj_loader())),
+    )]
+}
+
 pub fn make_adapter_to_render_to_disk(output_path: Box<Path>) -> anyhow::Result<Box<dyn WgpuGraphicalAdapterState>> {
-    let wgpu_obj_model_loader_adapter = Rc::new(ObjWgpuModelLoaderAdapter::new(
+    make_adapter_to_render_to_disk_with_loader(output_path, make_obj_loader(), DEFAULT_RENDER_TO_DISK_SIZE, 1)
+}
+
+pub fn make_obj_loader() -> Rc<dyn WgpuModelLoaderGateway> {
+    Rc::new(ObjWgpuModelLoaderAdapter::new(
         Box::from(env!("OUT_DIR")),
-    ));
-    let mut factories: HashMap<String, Box<dyn WgpuGraphicalAdapterPipelineFactory>> = HashMap::new();
-    factories.insert(
-        DEFAULT_PIPELINE_ID.to_string(),
-        Box::new(DefaultWgpuGraphicalAdapterPipelineFactory::new(wgpu_obj_model_loader_adapter)),
-    );
+    ))
+}
+
+pub fn make_adapter_to_render_to_disk_with_loader(
+    output_path: Box<Path>,
+    model_loader_gateway: Rc<dyn WgpuModelLoaderGateway>,
+    size: common::Dimentions,
+    sample_count: u32,
+) -> anyhow::Result<Box<dyn WgpuGraphicalAdapterState>> {
+    let pipelines = vec![PipelineRegistration::new(
+        DEFAULT_PIPELINE_ID,
+        DEFAULT_PIPELINE_ORDER,
+        Box::new(DefaultWgpuGraphicalAdapterPipelineFactory::new(model_loader_gateway)),
+    )];
+    make_adapter_to_render_to_disk_with_pipelines(output_path, pipelines, size, sample_count)
+}
+
+pub fn make_adapter_to_render_to_disk_with_pipelines(
+    output_path: Box<Path>,
+    pipelines: Vec<PipelineRegistration>,
+    size: common::Dimentions,
+    sample_count: u32,
+) -> anyhow::Result<Box<dyn WgpuGraphicalAdapterState>> {
     Ok(Box::new(WgpuGraphicalAdapterStateRenderToDisk::new(
-        common::Dimentions {
-            width: 512,
-            height: 512,
-        },
-        factories,
+        size,
+        sample_count,
+        &RendererConfig::from_env(),
+        make_default_camera(),
+        pipelines,
         output_path,
     ).block_on()?))
+}
 
-    
+// Looks at the origin from above and behind, the aspect ratio is set by the state
+pub fn make_default_camera() -> Box<dyn Camera> {
+    Box::new(PerspectiveCamera {
+        eye: (0.0, 6.0, 20.0).into(),
+        target: (0.0, 0.0, 0.0).into(),
+        up: cgmath::Vector3::unit_y(),
+        aspect: 1.0,
+        fovy: 45.0,
+        znear: 0.1,
+        zfar: 100.0,
+    })
 }
 
 pub fn load_cube_for_default_pipeline(
@@ -91,10 +189,7 @@ pub fn get_cube_instances_by_absolute_time(time: u32) -> Vec<Instance> {
                 cgmath::Quaternion::from_axis_angle(position_for_rotation.normalize(), cgmath::Deg(45.0))
             };
 
-            Instance {
-                position: cgmath::Vector3 { x, y, z },
-                rotation,
-            }
+            Instance::new(cgmath::Vector3 { x, y, z }, rotation)
         })
     }).collect::<Vec<_>>()
 }
\ No newline at end of file
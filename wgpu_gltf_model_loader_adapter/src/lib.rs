@@ -0,0 +1,527 @@
+use anyhow::Context;
+use cgmath::{InnerSpace, Matrix, SquareMatrix};
+use wgpu::util::DeviceExt;
+use common::Dimentions;
+use wgpu_graphical_adapter::gateways::{MaterialBindGroupBuilder, WgpuModelLoaderGateway};
+use wgpu_graphical_adapter::instance::{Instance, InstanceBuffer};
+use wgpu_graphical_adapter::mipmap::MipmapGenerator;
+use wgpu_graphical_adapter::model::{Material, Mesh, Model};
+use wgpu_graphical_adapter::texture::{Texture, TextureLoadOptions};
+use wgpu_graphical_adapter::vertex::{compute_tangents, ModelVertex};
+
+// Loads .gltf (with embedded or external buffers and images) and .glb files.
+// Node transforms are baked into the vertices, so a model is drawn as it is laid out in its default scene.
+pub struct GltfWgpuModelLoaderAdapter {
+    out_dir: Box<str>,
+    texture_load_options: TextureLoadOptions,
+}
+
+impl GltfWgpuModelLoaderAdapter {
+    pub fn new(
+        out_dir: Box<str>,
+    ) -> Self {
+        Self::new_with_texture_load_options(out_dir, TextureLoadOptions::default())
+    }
+
+    pub fn new_with_texture_load_options(
+        out_dir: Box<str>,
+        texture_load_options: TextureLoadOptions,
+    ) -> Self {
+        Self {
+            out_dir,
+            texture_load_options,
+        }
+    }
+
+    fn get_out_dir_path(&self, file_name: &str) -> std::path::PathBuf {
+        std::path::Path::new(self.out_dir.as_ref())
+            .join("res")
+            .join(file_name)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn load_material(
+        material: gltf::Material,
+        images: &[gltf::image::Data],
+        texture_load_options: TextureLoadOptions,
+        mipmap_generator: &mut MipmapGenerator,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        texture_bind_group_layout: &wgpu::BindGroupLayout,
+        bind_group_builder: &MaterialBindGroupBuilder,
+    ) -> anyhow::Result<Material> {
+        let pbr = material.pbr_metallic_roughness();
+        let diffuse_texture = match Self::decode_base_color_texture(&material, images)? {
+            Some((pixels, dimensions)) => Self::load_texture(
+                &pixels,
+                dimensions,
+                wgpu::TextureFormat::Rgba8UnormSrgb,
+                texture_load_options,
+                mipmap_generator,
+                device,
+                queue,
+            )?,
+            None => Self::make_base_color_texture(pbr.base_color_factor(), mipmap_generator, device, queue),
+        };
+        let normal_texture = match material.normal_texture() {
+            // Normal maps hold directions, not colors, so they must not be converted from sRGB.
+            Some(normal) => Some(Self::load_texture(
+                &Self::image_to_rgba8(&images[normal.texture().source().index()])?,
+                Self::image_dimentions(&images[normal.texture().source().index()]),
+                wgpu::TextureFormat::Rgba8Unorm,
+                texture_load_options,
+                mipmap_generator,
+                device,
+                queue,
+            )?),
+            None => None,
+        };
+        let bind_group = bind_group_builder(
+            device,
+            &diffuse_texture,
+            normal_texture.as_ref(),
+            texture_bind_group_layout
+        );
+
+        Ok(Material {
+            name: material.name().unwrap_or("unnamed").to_string(),
+            diffuse_texture,
+            normal_texture,
+            bind_group,
+        })
+    }
+
+    // The pixels of the base color texture multiplied by the base color factor, as glTF defines the base color.
+    // The default pipeline has no per material uniform, so the factor is applied once here.
+    fn decode_base_color_texture(
+        material: &gltf::Material,
+        images: &[gltf::image::Data],
+    ) -> anyhow::Result<Option<(Vec<u8>, Dimentions)>> {
+        let pbr = material.pbr_metallic_roughness();
+        let Some(info) = pbr.base_color_texture() else {
+            return Ok(None);
+        };
+        let image = &images[info.texture().source().index()];
+        let mut pixels = Self::image_to_rgba8(image)?;
+        let base_color_factor = pbr.base_color_factor();
+        if base_color_factor != [1.0; 4] {
+            // The texture is in sRGB and the factor in linear space
+            for pixel in pixels.chunks_exact_mut(4) {
+                for channel in 0..3 {
+                    let linear = srgb_to_linear(pixel[channel]) * base_color_factor[channel];
+                    pixel[channel] = linear_to_srgb(linear);
+                }
+                pixel[3] = (pixel[3] as f32 * base_color_factor[3].clamp(0.0, 1.0)).round() as u8;
+            }
+        }
+        Ok(Some((pixels, Self::image_dimentions(image))))
+    }
+
+    fn image_dimentions(image: &gltf::image::Data) -> Dimentions {
+        Dimentions {
+            width: image.width,
+            height: image.height,
+        }
+    }
+
+    fn load_texture(
+        pixels: &[u8],
+        dimensions: Dimentions,
+        format: wgpu::TextureFormat,
+        texture_load_options: TextureLoadOptions,
+        mipmap_generator: &mut MipmapGenerator,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> anyhow::Result<Texture> {
+        Ok(Texture::new_from_rgba8(
+            pixels,
+            dimensions,
+            format,
+            texture_load_options,
+            mipmap_generator,
+            device,
+            queue,
+        ))
+    }
+
+    // A 1x1 texture of the base color factor, which is in linear space.
+    fn make_base_color_texture(
+        base_color_factor: [f32; 4],
+        mipmap_generator: &mut MipmapGenerator,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Texture {
+        Texture::new_from_rgba8(
+            &base_color_factor.map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8),
+            Dimentions {
+                width: 1,
+                height: 1,
+            },
+            wgpu::TextureFormat::Rgba8Unorm,
+            TextureLoadOptions {
+                generate_mipmaps: false,
+            },
+            mipmap_generator,
+            device,
+            queue,
+        )
+    }
+
+    fn image_to_rgba8(image: &gltf::image::Data) -> anyhow::Result<Vec<u8>> {
+        use gltf::image::Format;
+        let (channels, bytes_per_channel) = match image.format {
+            Format::R8 => (1, 1),
+            Format::R8G8 => (2, 1),
+            Format::R8G8B8 => (3, 1),
+            Format::R8G8B8A8 => (4, 1),
+            Format::R16 => (1, 2),
+            Format::R16G16 => (2, 2),
+            Format::R16G16B16 => (3, 2),
+            Format::R16G16B16A16 => (4, 2),
+            Format::R32G32B32FLOAT => (3, 4),
+            Format::R32G32B32A32FLOAT => (4, 4),
+        };
+        let expected_size = (image.width * image.height) as usize * channels * bytes_per_channel;
+        if image.pixels.len() != expected_size {
+            return Err(anyhow::anyhow!(
+                "Unexpected image size: {} bytes, expected {} bytes", image.pixels.len(), expected_size
+            ));
+        }
+
+        let to_u8 = |bytes: &[u8]| -> u8 {
+            match bytes_per_channel {
+                1 => bytes[0],
+                2 => (u16::from_ne_bytes([bytes[0], bytes[1]]) >> 8) as u8,
+                _ => {
+                    let value = f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+                }
+            }
+        };
+
+        Ok(image.pixels
+            .chunks_exact(channels * bytes_per_channel)
+            .flat_map(|pixel| {
+                let values = pixel
+                    .chunks_exact(bytes_per_channel)
+                    .map(to_u8)
+                    .collect::<Vec<_>>();
+                match channels {
+                    // Grayscale
+                    1 => [values[0], values[0], values[0], 255],
+                    // Grayscale with alpha
+                    2 => [values[0], values[0], values[0], values[1]],
+                    3 => [values[0], values[1], values[2], 255],
+                    _ => [values[0], values[1], values[2], values[3]],
+                }
+            })
+            .collect())
+    }
+
+    fn load_node(
+        file_name: &str,
+        node: gltf::Node,
+        parent_transform: cgmath::Matrix4<f32>,
+        buffers: &[gltf::buffer::Data],
+        default_material: usize,
+        device: &wgpu::Device,
+        meshes: &mut Vec<Mesh>,
+    ) -> anyhow::Result<()> {
+        let transform = parent_transform * cgmath::Matrix4::from(node.transform().matrix());
+
+        if let Some(mesh) = node.mesh() {
+            let name = mesh.name().unwrap_or(file_name);
+            for primitive in mesh.primitives() {
+                if primitive.mode() != gltf::mesh::Mode::Triangles {
+                    log::warn!("Skipping primitive of {}, only triangles are supported: {:?}", name, primitive.mode());
+                    continue;
+                }
+                meshes.push(Self::load_primitive(
+                    name,
+                    &primitive,
+                    transform,
+                    buffers,
+                    default_material,
+                    device,
+                )?);
+            }
+        }
+
+        for child in node.children() {
+            Self::load_node(file_name, child, transform, buffers, default_material, device, meshes)?;
+        }
+        Ok(())
+    }
+
+    // The vertices have a single set of texture coordinates, the one of the base color texture wins
+    fn get_tex_coord_set(name: &str, material: &gltf::Material) -> u32 {
+        let base_color = material.pbr_metallic_roughness().base_color_texture().map(|info| info.tex_coord());
+        let normal = material.normal_texture().map(|info| info.tex_coord());
+        match (base_color, normal) {
+            (Some(base_color), Some(normal)) if base_color != normal => {
+                log::warn!(
+                    "Primitive of {} uses the texture coordinates {} for its base color and {} for its normal map, \
+                    only one set is supported, using {}",
+                    name, base_color, normal, base_color,
+                );
+                base_color
+            }
+            (Some(set), _) | (None, Some(set)) => set,
+            (None, None) => 0,
+        }
+    }
+
+    fn load_primitive(
+        name: &str,
+        primitive: &gltf::Primitive,
+        transform: cgmath::Matrix4<f32>,
+        buffers: &[gltf::buffer::Data],
+        default_material: usize,
+        device: &wgpu::Device,
+    ) -> anyhow::Result<Mesh> {
+        let reader = primitive.reader(|buffer| Some(buffers[buffer.index()].0.as_slice()));
+
+        let positions = reader
+            .read_positions()
+            .with_context(|| format!("Primitive of {} has no positions", name))?
+            .collect::<Vec<_>>();
+        let normals = reader
+            .read_normals()
+            .map(|normals| normals.collect::<Vec<_>>());
+        let tex_coords = reader
+            .read_tex_coords(Self::get_tex_coord_set(name, &primitive.material()))
+            .map(|tex_coords| tex_coords.into_f32().collect::<Vec<_>>());
+        // The spec requires ignoring the tangents of the primitives without normals
+        let tangents = reader
+            .read_tangents()
+            .filter(|_| normals.is_some())
+            .map(|tangents| tangents.collect::<Vec<_>>());
+        let mut indices = match reader.read_indices() {
+            Some(indices) => indices.into_u32().collect::<Vec<_>>(),
+            None => (0..positions.len() as u32).collect(),
+        };
+        // Flat normals need a vertex per triangle corner, the shared vertices are split
+        let vertex_sources = match normals {
+            Some(_) => (0..positions.len()).collect::<Vec<_>>(),
+            None => indices.iter().map(|index| *index as usize).collect(),
+        };
+        if normals.is_none() {
+            indices = (0..vertex_sources.len() as u32).collect();
+        }
+
+        // Normals must be transformed with the inverse transpose to stay perpendicular under non uniform scaling
+        let linear_transform = cgmath::Matrix3::from_cols(
+            transform.x.truncate(),
+            transform.y.truncate(),
+            transform.z.truncate(),
+        );
+        let normal_transform = linear_transform
+            .invert()
+            .map(|inverse| inverse.transpose())
+            .unwrap_or(linear_transform);
+
+        let mut vertices = vertex_sources
+            .iter()
+            .map(|&i| {
+                let position = transform * cgmath::Vector3::from(positions[i]).extend(1.0);
+                let normal = normals
+                    .as_ref()
+                    .map(|normals| (normal_transform * cgmath::Vector3::from(normals[i])).normalize())
+                    // Computed below from the triangles
+                    .unwrap_or(cgmath::Vector3::new(0.0, 0.0, 0.0));
+                let (tangent, bitangent) = match &tangents {
+                    Some(tangents) => {
+                        let [x, y, z, handedness] = tangents[i];
+                        let tangent = (linear_transform * cgmath::Vector3::new(x, y, z)).normalize();
+                        (tangent, normal.cross(tangent) * handedness)
+                    }
+                    // Computed below from the triangles
+                    None => (cgmath::Vector3::new(0.0, 0.0, 0.0), cgmath::Vector3::new(0.0, 0.0, 0.0)),
+                };
+                ModelVertex {
+                    position: position.truncate().into(),
+                    tex_coords: tex_coords.as_ref().map(|tex_coords| tex_coords[i]).unwrap_or([0.0, 0.0]),
+                    normal: normal.into(),
+                    tangent: tangent.into(),
+                    bitangent: bitangent.into(),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        // A mirroring transform flips the triangles, restore their counter clockwise winding
+        if linear_transform.determinant() < 0.0 {
+            for triangle in indices.chunks_exact_mut(3) {
+                triangle.swap(1, 2);
+            }
+        }
+
+        if normals.is_none() {
+            compute_flat_normals(&mut vertices, &indices);
+        }
+        if tangents.is_none() && tex_coords.is_some() {
+            compute_tangents(&mut vertices, &indices);
+        }
+
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(&format!("{:?} Vertex Buffer", name)),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(&format!("{:?} Index Buffer", name)),
+            contents: bytemuck::cast_slice(&indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        Ok(Mesh {
+            name: name.to_string(),
+            vertex_buffer,
+            index_buffer,
+            num_elements: indices.len() as u32,
+            material: primitive.material().index().unwrap_or(default_material),
+        })
+    }
+}
+
+impl WgpuModelLoaderGateway for GltfWgpuModelLoaderAdapter {
+    fn load_model_sync(
+        &self,
+        file_name: &str,
+        instances: Vec<Instance>,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        texture_bind_group_layout: &wgpu::BindGroupLayout,
+        bind_group_builder: MaterialBindGroupBuilder,
+        mipmap_generator: &mut MipmapGenerator,
+    ) -> anyhow::Result<Model> {
+        let (document, buffers, images) = gltf::import(self.get_out_dir_path(file_name))
+            .with_context(|| format!("Failed to import glTF file: {}", file_name))?;
+
+        let mut materials = Vec::new();
+        for material in document.materials() {
+            materials.push(Self::load_material(
+                material,
+                &images,
+                self.texture_load_options,
+                mipmap_generator,
+                device,
+                queue,
+                texture_bind_group_layout,
+                &bind_group_builder,
+            )?);
+        }
+        // Used by the primitives without a material, white as the glTF default material
+        let default_material = materials.len();
+        let default_diffuse_texture = Self::make_base_color_texture([1.0; 4], mipmap_generator, device, queue);
+        materials.push(Material {
+            name: "default".to_string(),
+            bind_group: bind_group_builder(
+                device,
+                &default_diffuse_texture,
+                None,
+                texture_bind_group_layout
+            ),
+            diffuse_texture: default_diffuse_texture,
+            normal_texture: None,
+        });
+
+        let scene = document
+            .default_scene()
+            .or_else(|| document.scenes().next())
+            .with_context(|| format!("glTF file has no scene: {}", file_name))?;
+
+        let mut meshes = Vec::new();
+        for node in scene.nodes() {
+            Self::load_node(
+                file_name,
+                node,
+                cgmath::Matrix4::identity(),
+                &buffers,
+                default_material,
+                device,
+                &mut meshes,
+            )?;
+        }
+
+        let instances = InstanceBuffer::new(&instances, device, queue);
+
+        Ok(Model {
+            meshes,
+            materials,
+            instances,
+        })
+    }
+}
+
+// The vertices must not be shared between triangles, each gets the normal of its triangle.
+fn compute_flat_normals(vertices: &mut [ModelVertex], indices: &[u32]) {
+    for triangle in indices.chunks_exact(3) {
+        let pos0: cgmath::Vector3<f32> = vertices[triangle[0] as usize].position.into();
+        let pos1: cgmath::Vector3<f32> = vertices[triangle[1] as usize].position.into();
+        let pos2: cgmath::Vector3<f32> = vertices[triangle[2] as usize].position.into();
+        let normal = (pos1 - pos0).cross(pos2 - pos0);
+        // Degenerated triangles are not visible anyway
+        if normal.magnitude2() == 0.0 {
+            continue;
+        }
+        let normal = normal.normalize();
+        for index in triangle {
+            vertices[*index as usize].normal = normal.into();
+        }
+    }
+}
+
+fn srgb_to_linear(channel: u8) -> f32 {
+    let channel = channel as f32 / 255.0;
+    if channel <= 0.04045 {
+        channel / 12.92
+    } else {
+        ((channel + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(channel: f32) -> u8 {
+    let channel = channel.clamp(0.0, 1.0);
+    let srgb = if channel <= 0.0031308 {
+        channel * 12.92
+    } else {
+        1.055 * channel.powf(1.0 / 2.4) - 0.055
+    };
+    (srgb * 255.0).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base_color_factor_multiplies_the_base_color_texture() {
+        let directory = std::env::temp_dir().join("wgpu_gltf_model_loader_adapter_base_color_factor");
+        std::fs::create_dir_all(&directory).unwrap();
+        image::RgbaImage::from_pixel(2, 1, image::Rgba([255, 128, 0, 255]))
+            .save(directory.join("base_color.png"))
+            .unwrap();
+        std::fs::write(directory.join("material.gltf"), r#"{
+            "asset": { "version": "2.0" },
+            "images": [{ "uri": "base_color.png" }],
+            "textures": [{ "source": 0 }],
+            "materials": [{
+                "pbrMetallicRoughness": {
+                    "baseColorTexture": { "index": 0 },
+                    "baseColorFactor": [0.5, 1.0, 0.25, 0.5]
+                }
+            }]
+        }"#).unwrap();
+
+        let (document, _, images) = gltf::import(directory.join("material.gltf")).unwrap();
+        let material = document.materials().next().unwrap();
+        let (pixels, dimensions) = GltfWgpuModelLoaderAdapter::decode_base_color_texture(&material, &images)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!((dimensions.width, dimensions.height), (2, 1));
+        // Red is halved in linear space, green is kept, blue stays black and alpha is halved
+        assert_eq!(&pixels[..4], &[linear_to_srgb(0.5), 128, 0, 128]);
+        assert_eq!(&pixels[..4], &pixels[4..]);
+    }
+}
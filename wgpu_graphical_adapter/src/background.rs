@@ -0,0 +1,194 @@
+use wgpu::util::DeviceExt;
+use crate::texture::Texture;
+
+// What the render target is filled with before the pipelines draw.
+// Colors are linear, like wgpu::Color, they are converted when the target is sRGB.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Background {
+    Solid(wgpu::Color),
+    // Fully transparent black, to composite the output over something else. Only useful when rendering
+    // to disk or when the surface supports a non opaque alpha mode.
+    Transparent,
+    VerticalGradient {
+        top: wgpu::Color,
+        bottom: wgpu::Color,
+    },
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Background::Solid(wgpu::Color {
+            r: 0.1,
+            g: 0.2,
+            b: 0.3,
+            a: 1.0,
+        })
+    }
+}
+
+impl Background {
+    pub fn clear_color(&self) -> wgpu::Color {
+        match self {
+            Background::Solid(color) => *color,
+            Background::Transparent => wgpu::Color::TRANSPARENT,
+            // Entirely covered by the gradient
+            Background::VerticalGradient { top, .. } => *top,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct BackgroundUniform {
+    top_color: [f32; 4],
+    bottom_color: [f32; 4],
+}
+
+impl BackgroundUniform {
+    fn new(background: &Background) -> Self {
+        let (top, bottom) = match background {
+            Background::VerticalGradient { top, bottom } => (*top, *bottom),
+            _ => (background.clear_color(), background.clear_color()),
+        };
+        BackgroundUniform {
+            top_color: Self::color_to_array(top),
+            bottom_color: Self::color_to_array(bottom),
+        }
+    }
+
+    fn color_to_array(color: wgpu::Color) -> [f32; 4] {
+        [color.r as f32, color.g as f32, color.b as f32, color.a as f32]
+    }
+}
+
+// Clears the render target and draws the gradient when there is one.
+pub struct BackgroundRenderer {
+    background: Background,
+    gradient_pipeline: wgpu::RenderPipeline,
+    uniform_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+
+impl BackgroundRenderer {
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+        background: Background,
+    ) -> BackgroundRenderer {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Background Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("background.wgsl").into()),
+        });
+
+        let uniform_buffer = device.create_buffer_init(
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("Background Buffer"),
+                contents: bytemuck::cast_slice(&[BackgroundUniform::new(&background)]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            }
+        );
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }
+            ],
+            label: Some("Background Bind Group Layout"),
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                }
+            ],
+            label: Some("Background Bind Group"),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Background Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let gradient_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Background Gradient Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                ..Default::default()
+            },
+            // Shares the render pass of the other pipelines, but must not hide anything
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: Texture::DEPTH_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache: None,
+        });
+
+        BackgroundRenderer {
+            background,
+            gradient_pipeline,
+            uniform_buffer,
+            bind_group,
+        }
+    }
+
+    pub fn get_background(&self) -> &Background {
+        &self.background
+    }
+
+    pub fn set_background(&mut self, background: Background, queue: &wgpu::Queue) {
+        self.background = background;
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[BackgroundUniform::new(&background)]));
+    }
+
+    pub fn clear_color(&self) -> wgpu::Color {
+        self.background.clear_color()
+    }
+
+    // Must be called before the pipelines render
+    pub fn render<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        if let Background::VerticalGradient { .. } = self.background {
+            render_pass.set_pipeline(&self.gradient_pipeline);
+            render_pass.set_bind_group(0, &self.bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+    }
+}
@@ -0,0 +1,29 @@
+// Fills the render target with a vertical gradient, drawn before everything else
+struct BackgroundUniform {
+    top_color: vec4<f32>,
+    bottom_color: vec4<f32>,
+}
+@group(0) @binding(0)
+var<uniform> background: BackgroundUniform;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    // A single triangle covering the whole render target
+    let tex_coords = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+
+    var out: VertexOutput;
+    out.tex_coords = tex_coords;
+    // On the far plane, the depth test is disabled anyway
+    out.clip_position = vec4<f32>(tex_coords * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 1.0, 1.0);
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return mix(background.top_color, background.bottom_color, in.tex_coords.y);
+}
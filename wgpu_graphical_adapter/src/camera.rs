@@ -1,26 +1,116 @@
+// Maps the OpenGL depth range of cgmath's projections, -1 to 1, to the 0 to 1 range of wgpu.
+// Matrix4::new takes the matrix column by column.
 #[rustfmt::skip]
 pub const OPENGL_TO_WGPU_MATRIX: cgmath::Matrix4<f32> = cgmath::Matrix4::new(
     1.0, 0.0, 0.0, 0.0,
     0.0, 1.0, 0.0, 0.0,
-    0.0, 0.0, 0.5, 0.5,
-    0.0, 0.0, 0.0, 1.0,
+    0.0, 0.0, 0.5, 0.0,
+    0.0, 0.0, 0.5, 1.0,
 );
 
+// What the pipelines need from a camera, whatever its projection.
+pub trait Camera {
+    fn build_view_matrix(&self) -> cgmath::Matrix4<f32>;
+    fn build_projection_matrix(&self) -> cgmath::Matrix4<f32>;
+    // Where the camera is, in world space.
+    fn position(&self) -> cgmath::Point3<f32>;
+    fn target(&self) -> cgmath::Point3<f32>;
+    fn up(&self) -> cgmath::Vector3<f32>;
+    fn look_at(&mut self, eye: cgmath::Point3<f32>, target: cgmath::Point3<f32>, up: cgmath::Vector3<f32>);
+    // Width divided by height of the render target, updated when it is resized.
+    fn set_aspect(&mut self, aspect: f32);
+
+    fn build_view_projection_matrix(&self) -> cgmath::Matrix4<f32> {
+        self.build_projection_matrix() * self.build_view_matrix()
+    }
+}
+
 pub struct PerspectiveCamera {
     pub eye: cgmath::Point3<f32>, // By eye we mean the position of the camera.
     pub target: cgmath::Point3<f32>, // By target we mean the point the camera is looking at.
     pub up: cgmath::Vector3<f32>, // By up we mean the direction that is up for the camera.
     pub aspect: f32, // The aspect ratio of the camera.
-    pub fovy: f32, // The field of view of the camera in radians.
+    pub fovy: f32, // The field of view of the camera in degrees.
     pub znear: f32, // The near clipping plane of the camera.
     pub zfar: f32,  // The far clipping plane of the camera.
 }
 
-impl PerspectiveCamera {
-    pub fn build_view_projection_matrix(&self) -> cgmath::Matrix4<f32> {
-        let view = cgmath::Matrix4::look_at_rh(self.eye, self.target, self.up);
+impl Camera for PerspectiveCamera {
+    fn build_view_matrix(&self) -> cgmath::Matrix4<f32> {
+        cgmath::Matrix4::look_at_rh(self.eye, self.target, self.up)
+    }
+
+    fn build_projection_matrix(&self) -> cgmath::Matrix4<f32> {
         let proj = cgmath::perspective(cgmath::Deg(self.fovy), self.aspect, self.znear, self.zfar);
-        return OPENGL_TO_WGPU_MATRIX * proj * view;
+        OPENGL_TO_WGPU_MATRIX * proj
+    }
+
+    fn position(&self) -> cgmath::Point3<f32> {
+        self.eye
+    }
+
+    fn target(&self) -> cgmath::Point3<f32> {
+        self.target
+    }
+
+    fn up(&self) -> cgmath::Vector3<f32> {
+        self.up
+    }
+
+    fn look_at(&mut self, eye: cgmath::Point3<f32>, target: cgmath::Point3<f32>, up: cgmath::Vector3<f32>) {
+        self.eye = eye;
+        self.target = target;
+        self.up = up;
+    }
+
+    fn set_aspect(&mut self, aspect: f32) {
+        self.aspect = aspect;
+    }
+}
+
+// Keeps the size of things regardless of their distance, for top-down maps, CAD-style views and 2D overlays.
+pub struct OrthographicCamera {
+    pub eye: cgmath::Point3<f32>,
+    pub target: cgmath::Point3<f32>,
+    pub up: cgmath::Vector3<f32>,
+    pub height: f32, // The height of the visible area in world units, its width follows the aspect ratio.
+    pub aspect: f32,
+    pub znear: f32,
+    pub zfar: f32,
+}
+
+impl Camera for OrthographicCamera {
+    fn build_view_matrix(&self) -> cgmath::Matrix4<f32> {
+        cgmath::Matrix4::look_at_rh(self.eye, self.target, self.up)
+    }
+
+    fn build_projection_matrix(&self) -> cgmath::Matrix4<f32> {
+        let half_height = self.height / 2.0;
+        let half_width = half_height * self.aspect;
+        let proj = cgmath::ortho(-half_width, half_width, -half_height, half_height, self.znear, self.zfar);
+        OPENGL_TO_WGPU_MATRIX * proj
+    }
+
+    fn position(&self) -> cgmath::Point3<f32> {
+        self.eye
+    }
+
+    fn target(&self) -> cgmath::Point3<f32> {
+        self.target
+    }
+
+    fn up(&self) -> cgmath::Vector3<f32> {
+        self.up
+    }
+
+    fn look_at(&mut self, eye: cgmath::Point3<f32>, target: cgmath::Point3<f32>, up: cgmath::Vector3<f32>) {
+        self.eye = eye;
+        self.target = target;
+        self.up = up;
+    }
+
+    fn set_aspect(&mut self, aspect: f32) {
+        self.aspect = aspect;
     }
 }
 
@@ -29,6 +119,8 @@ impl PerspectiveCamera {
 // This is so we can store this in a buffer
 #[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct CameraUniform {
+    // Needed for specular lighting, a vec4 because of the uniforms 16 byte alignment requirement.
+    pub view_position: [f32; 4],
     // We can't use cgmath with bytemuck directly, so we'll have
     // to convert the Matrix4 into a 4x4 f32 array
     pub view_proj: [[f32; 4]; 4],
@@ -38,12 +130,59 @@ impl CameraUniform {
     pub fn new() -> Self {
         use cgmath::SquareMatrix;
         Self {
+            view_position: [0.0; 4],
             view_proj: cgmath::Matrix4::identity().into(),
         }
     }
 
-    pub fn update_view_proj(&mut self, camera: &PerspectiveCamera) {
+    pub fn update_view_proj(&mut self, camera: &dyn Camera) {
+        self.view_position = camera.position().to_homogeneous().into();
         self.view_proj = camera.build_view_projection_matrix().into();
     }
 }
- 
\ No newline at end of file
+ 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The depth of a point halfway between the clipping planes, straight ahead of the camera.
+    fn project_depth(camera: &dyn Camera) -> f32 {
+        use cgmath::InnerSpace;
+        let direction = (camera.target() - camera.position()).normalize();
+        let point = camera.position() + direction * 5.5;
+        let clip = camera.build_view_projection_matrix() * point.to_homogeneous();
+        clip.z / clip.w
+    }
+
+    #[test]
+    fn perspective_depth_is_in_wgpu_range() {
+        let camera = PerspectiveCamera {
+            eye: (0.0, 1.0, 2.0).into(),
+            target: (0.0, 0.0, 0.0).into(),
+            up: cgmath::Vector3::unit_y(),
+            aspect: 1.0,
+            fovy: 45.0,
+            znear: 1.0,
+            zfar: 10.0,
+        };
+        let depth = project_depth(&camera);
+        assert!((0.0..=1.0).contains(&depth), "depth {}", depth);
+    }
+
+    #[test]
+    fn orthographic_depth_is_in_wgpu_range() {
+        let camera = OrthographicCamera {
+            eye: (0.0, 20.0, 0.0).into(),
+            target: (0.0, 0.0, 0.0).into(),
+            up: -cgmath::Vector3::unit_z(),
+            height: 10.0,
+            aspect: 1.0,
+            znear: 1.0,
+            zfar: 10.0,
+        };
+        let depth = project_depth(&camera);
+        assert!((0.0..=1.0).contains(&depth), "depth {}", depth);
+        // Linear, halfway through the planes
+        assert!((depth - 0.5).abs() < 1e-5, "depth {}", depth);
+    }
+}
@@ -0,0 +1,205 @@
+use std::time::Duration;
+
+use cgmath::InnerSpace;
+use common::input::{InputState, Key, MouseButton};
+
+use crate::camera::Camera;
+
+// What the controllers need to know about the user input of a frame, whatever the windowing library.
+#[derive(Clone, Debug)]
+pub struct CameraControllerInput {
+    pub forward: bool,
+    pub backward: bool,
+    pub left: bool,
+    pub right: bool,
+    pub up: bool,
+    pub down: bool,
+    // Held to rotate the camera with the cursor, so the cursor stays usable otherwise.
+    pub look: bool,
+    // Cursor movement since the last frame, in pixels, +y is down.
+    pub look_delta: cgmath::Vector2<f32>,
+    // Scroll since the last frame, in lines, positive away from the user.
+    pub scroll_delta: f32,
+}
+
+impl Default for CameraControllerInput {
+    fn default() -> Self {
+        CameraControllerInput {
+            forward: false,
+            backward: false,
+            left: false,
+            right: false,
+            up: false,
+            down: false,
+            look: false,
+            look_delta: cgmath::Vector2::new(0.0, 0.0),
+            scroll_delta: 0.0,
+        }
+    }
+}
+
+impl CameraControllerInput {
+    // WASD moves, space goes up, left shift goes down and the right mouse button looks around.
+    pub fn from_input_state(input_state: &InputState) -> Self {
+        let (cursor_x, cursor_y) = input_state.cursor_delta();
+        CameraControllerInput {
+            forward: input_state.is_key_pressed(Key::W),
+            backward: input_state.is_key_pressed(Key::S),
+            left: input_state.is_key_pressed(Key::A),
+            right: input_state.is_key_pressed(Key::D),
+            up: input_state.is_key_pressed(Key::Space),
+            down: input_state.is_key_pressed(Key::LeftShift),
+            look: input_state.is_mouse_button_pressed(MouseButton::Right),
+            look_delta: cgmath::Vector2::new(cursor_x, cursor_y),
+            scroll_delta: input_state.scroll_delta().1,
+        }
+    }
+
+    // Clears the movements accumulated during the frame, the held keys stay held.
+    pub fn end_frame(&mut self) {
+        self.look_delta = cgmath::Vector2::new(0.0, 0.0);
+        self.scroll_delta = 0.0;
+    }
+}
+
+// Moves a camera from the user input. The world is y-up.
+pub trait CameraController {
+    // delta_time is the time since the last update, so the speeds don't depend on the frame rate.
+    fn update(&mut self, camera: &mut dyn Camera, input: &CameraControllerInput, delta_time: Duration);
+}
+
+// Turns around the target, the cursor rotates and the scroll zooms.
+pub struct OrbitCameraController {
+    pub rotate_speed: f32, // Radians per pixel.
+    pub key_rotate_speed: f32, // Radians per second, for left and right.
+    pub zoom_speed: f32, // Fraction of the distance per line.
+    pub min_distance: f32,
+    pub max_distance: f32,
+}
+
+impl Default for OrbitCameraController {
+    fn default() -> Self {
+        OrbitCameraController {
+            rotate_speed: 0.005,
+            key_rotate_speed: 1.0,
+            zoom_speed: 0.1,
+            min_distance: 1.0,
+            max_distance: 100.0,
+        }
+    }
+}
+
+impl CameraController for OrbitCameraController {
+    fn update(&mut self, camera: &mut dyn Camera, input: &CameraControllerInput, delta_time: Duration) {
+        let target = camera.target();
+        let offset = camera.position() - target;
+        let (mut yaw, mut pitch) = yaw_pitch(offset);
+        if input.look {
+            yaw += input.look_delta.x * self.rotate_speed;
+            pitch += input.look_delta.y * self.rotate_speed;
+        }
+        yaw += axis(input.left, input.right) * self.key_rotate_speed * delta_time.as_secs_f32();
+        let pitch = pitch.clamp(-MAX_LOOK_AT_PITCH, MAX_LOOK_AT_PITCH);
+
+        let distance = (offset.magnitude() * (1.0 - input.scroll_delta * self.zoom_speed))
+            .clamp(self.min_distance, self.max_distance);
+        camera.look_at(target + direction(yaw, pitch) * distance, target, cgmath::Vector3::unit_y());
+    }
+}
+
+// Flies where the camera looks, WASD moves, up and down go along the world y axis and the cursor looks around.
+pub struct FlyCameraController {
+    pub speed: f32, // Units per second.
+    pub look_speed: f32, // Radians per pixel.
+}
+
+impl Default for FlyCameraController {
+    fn default() -> Self {
+        FlyCameraController {
+            speed: 10.0,
+            look_speed: 0.003,
+        }
+    }
+}
+
+impl CameraController for FlyCameraController {
+    fn update(&mut self, camera: &mut dyn Camera, input: &CameraControllerInput, delta_time: Duration) {
+        let (yaw, pitch) = look(camera, input, self.look_speed, MAX_LOOK_AT_PITCH);
+        let forward = direction(yaw, pitch);
+        let right = forward.cross(cgmath::Vector3::unit_y()).normalize();
+        let movement = forward * axis(input.backward, input.forward)
+            + right * axis(input.left, input.right)
+            + cgmath::Vector3::unit_y() * axis(input.down, input.up);
+        move_camera(camera, forward, movement, self.speed * delta_time.as_secs_f32());
+    }
+}
+
+// Walks on the horizontal plane whatever the pitch, which is limited so the user can't look upside down.
+pub struct FirstPersonCameraController {
+    pub speed: f32, // Units per second.
+    pub look_speed: f32, // Radians per pixel.
+    pub max_pitch: cgmath::Rad<f32>,
+}
+
+impl Default for FirstPersonCameraController {
+    fn default() -> Self {
+        FirstPersonCameraController {
+            speed: 5.0,
+            look_speed: 0.003,
+            max_pitch: cgmath::Deg(80.0).into(),
+        }
+    }
+}
+
+impl CameraController for FirstPersonCameraController {
+    fn update(&mut self, camera: &mut dyn Camera, input: &CameraControllerInput, delta_time: Duration) {
+        let max_pitch = self.max_pitch.0.min(MAX_LOOK_AT_PITCH);
+        let (yaw, pitch) = look(camera, input, self.look_speed, max_pitch);
+        let walk_forward = direction(yaw, 0.0);
+        let right = walk_forward.cross(cgmath::Vector3::unit_y());
+        let movement = walk_forward * axis(input.backward, input.forward) + right * axis(input.left, input.right);
+        move_camera(camera, direction(yaw, pitch), movement, self.speed * delta_time.as_secs_f32());
+    }
+}
+
+// Looking straight up or down makes the view matrix degenerate, as the direction and the up vector are aligned.
+const MAX_LOOK_AT_PITCH: f32 = std::f32::consts::FRAC_PI_2 - 0.01;
+
+// -1, 0 or 1
+fn axis(negative: bool, positive: bool) -> f32 {
+    (positive as i32 - negative as i32) as f32
+}
+
+// The yaw and pitch of the camera after applying the cursor movement, in radians.
+fn look(camera: &dyn Camera, input: &CameraControllerInput, look_speed: f32, max_pitch: f32) -> (f32, f32) {
+    let (mut yaw, mut pitch) = yaw_pitch(camera.target() - camera.position());
+    if input.look {
+        yaw += input.look_delta.x * look_speed;
+        pitch -= input.look_delta.y * look_speed;
+    }
+    (yaw, pitch.clamp(-max_pitch, max_pitch))
+}
+
+// Moves the eye and the target together, diagonals are not faster.
+fn move_camera(camera: &mut dyn Camera, forward: cgmath::Vector3<f32>, movement: cgmath::Vector3<f32>, distance: f32) {
+    let movement = if movement.magnitude2() > 0.0 {
+        movement.normalize() * distance
+    } else {
+        movement
+    };
+    let eye = camera.position() + movement;
+    camera.look_at(eye, eye + forward, cgmath::Vector3::unit_y());
+}
+
+fn yaw_pitch(direction: cgmath::Vector3<f32>) -> (f32, f32) {
+    // The eye is on the target, any direction will do
+    if direction.magnitude2() == 0.0 {
+        return (0.0, 0.0);
+    }
+    let direction = direction.normalize();
+    (direction.z.atan2(direction.x), direction.y.clamp(-1.0, 1.0).asin())
+}
+
+fn direction(yaw: f32, pitch: f32) -> cgmath::Vector3<f32> {
+    cgmath::Vector3::new(pitch.cos() * yaw.cos(), pitch.sin(), pitch.cos() * yaw.sin())
+}
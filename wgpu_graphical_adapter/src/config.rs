@@ -0,0 +1,83 @@
+// Whether a software adapter (lavapipe, llvmpipe, WARP...) may be used.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FallbackAdapter {
+    // Only hardware adapters are accepted.
+    Never,
+    // A hardware adapter is preferred, a software one is used when there is none (headless CI).
+    Allow,
+    // Only a software adapter is accepted, useful to get the same images on every machine.
+    Force,
+}
+
+impl FallbackAdapter {
+    fn from_env() -> Option<FallbackAdapter> {
+        let value = std::env::var("WGPU_FALLBACK_ADAPTER").ok()?;
+        match value.to_lowercase().as_str() {
+            "never" | "0" | "false" => Some(FallbackAdapter::Never),
+            "allow" => Some(FallbackAdapter::Allow),
+            "force" | "1" | "true" => Some(FallbackAdapter::Force),
+            _ => {
+                log::warn!("Ignoring unknown WGPU_FALLBACK_ADAPTER value: {}, expected never, allow or force", value);
+                None
+            }
+        }
+    }
+}
+
+// How the wgpu instance, adapter and device are created by the states.
+#[derive(Clone, Debug)]
+pub struct RendererConfig {
+    pub backends: wgpu::Backends,
+    pub power_preference: wgpu::PowerPreference,
+    pub fallback_adapter: FallbackAdapter,
+    pub required_features: wgpu::Features,
+    pub required_limits: wgpu::Limits,
+    // Only used by the states rendering to a window, falls back to Fifo when the surface doesn't support it.
+    pub present_mode: wgpu::PresentMode,
+}
+
+impl Default for RendererConfig {
+    fn default() -> Self {
+        RendererConfig {
+            backends: wgpu::Backends::PRIMARY,
+            power_preference: wgpu::PowerPreference::default(),
+            fallback_adapter: FallbackAdapter::Allow,
+            required_features: wgpu::Features::empty(),
+            required_limits: wgpu::Limits::default(),
+            // vsync, always supported
+            present_mode: wgpu::PresentMode::Fifo,
+        }
+    }
+}
+
+impl RendererConfig {
+    // The defaults, overridden by WGPU_BACKEND (e.g. "vulkan", "metal,dx12"),
+    // WGPU_POWER_PREF ("low", "high" or "none"), WGPU_FALLBACK_ADAPTER ("never", "allow" or "force")
+    // and WGPU_PRESENT_MODE ("fifo", "mailbox", "immediate", "auto_vsync" or "auto_no_vsync").
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        RendererConfig {
+            backends: wgpu::util::backend_bits_from_env().unwrap_or(default.backends),
+            power_preference: wgpu::util::power_preference_from_env().unwrap_or(default.power_preference),
+            fallback_adapter: FallbackAdapter::from_env().unwrap_or(default.fallback_adapter),
+            present_mode: present_mode_from_env().unwrap_or(default.present_mode),
+            ..default
+        }
+    }
+}
+
+fn present_mode_from_env() -> Option<wgpu::PresentMode> {
+    let value = std::env::var("WGPU_PRESENT_MODE").ok()?;
+    match value.to_lowercase().as_str() {
+        "fifo" => Some(wgpu::PresentMode::Fifo),
+        "fifo_relaxed" => Some(wgpu::PresentMode::FifoRelaxed),
+        "mailbox" => Some(wgpu::PresentMode::Mailbox),
+        "immediate" => Some(wgpu::PresentMode::Immediate),
+        "auto_vsync" => Some(wgpu::PresentMode::AutoVsync),
+        "auto_no_vsync" => Some(wgpu::PresentMode::AutoNoVsync),
+        _ => {
+            log::warn!("Ignoring unknown WGPU_PRESENT_MODE value: {}", value);
+            None
+        }
+    }
+}
@@ -1,11 +1,13 @@
-use std::collections::HashMap;
 use std::rc::Rc;
 
 use wgpu::util::DeviceExt;
 
-use crate::camera::{CameraUniform, PerspectiveCamera};
-use crate::gateways::WgpuModelLoaderGateway;
+use crate::camera::{Camera, CameraUniform};
+use crate::errors::WgpuGraphicalAdapterError;
+use crate::gateways::{MaterialBindGroupBuilder, WgpuModelLoaderGateway};
 use crate::instance::{Instance, InstanceRaw};
+use crate::light::{Light, LightsUniform};
+use crate::mipmap::MipmapGenerator;
 use crate::model::Model;
 use crate::pipeline::{WgpuGraphicalAdapterPipeline, WgpuGraphicalAdapterPipelineFactory};
 use crate::texture::Texture;
@@ -29,33 +31,55 @@ impl WgpuGraphicalAdapterPipelineFactory for DefaultWgpuGraphicalAdapterPipeline
     fn create(
         &self,
         device: &wgpu::Device,
+        queue: &wgpu::Queue,
         format: wgpu::TextureFormat,
-        camera: &PerspectiveCamera,
+        sample_count: u32,
+        camera: &dyn Camera,
+        lights: &[Light],
     ) -> Box<dyn WgpuGraphicalAdapterPipeline> {
         Box::new(DefaultWgpuGraphicalAdapterPipeline::new(
             device,
+            queue,
             format,
+            sample_count,
             camera,
+            lights,
             self.model_loader_gateway.clone(),
         ))
     }
 }
 
+struct OrderedModel {
+    id: String,
+    order: i32,
+    model: Model,
+}
+
 pub struct DefaultWgpuGraphicalAdapterPipeline {
     model_loader_gateway: Rc<dyn WgpuModelLoaderGateway>,
     pipeline: wgpu::RenderPipeline,
     texture_bind_group_layout: wgpu::BindGroupLayout,
-    models: HashMap<String, Model>,
+    flat_normal_texture: Rc<Texture>,
+    // Created with the first model
+    mipmap_generator: Option<MipmapGenerator>,
+    // Sorted by order, see set_model_order
+    models: Vec<OrderedModel>,
     camera_uniform: CameraUniform,
     camera_buffer: wgpu::Buffer,
     camera_bind_group: wgpu::BindGroup,
+    lights_uniform: LightsUniform,
+    lights_buffer: wgpu::Buffer,
+    lights_bind_group: wgpu::BindGroup,
 }
 
 impl DefaultWgpuGraphicalAdapterPipeline {
     pub fn new(
         device: &wgpu::Device,
+        queue: &wgpu::Queue,
         format: wgpu::TextureFormat,
-        camera: &PerspectiveCamera,
+        sample_count: u32,
+        camera: &dyn Camera,
+        lights: &[Light],
         model_loader_gateway: Rc<dyn WgpuModelLoaderGateway>,
     ) -> DefaultWgpuGraphicalAdapterPipeline {
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
@@ -84,13 +108,32 @@ impl DefaultWgpuGraphicalAdapterPipeline {
                         ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
                         count: None,
                     },
+                    // Normal map
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
                 ],
                 label: Some("Default Render Pipeline Texture Bind Group Layout"),
             });
 
 
+        let flat_normal_texture = Rc::new(Texture::new_flat_normal_texture(device, queue));
+
         let mut camera_uniform = CameraUniform::new();
-        camera_uniform.update_view_proj(&camera);
+        camera_uniform.update_view_proj(camera);
 
         let camera_buffer = device.create_buffer_init(
             &wgpu::util::BufferInitDescriptor {
@@ -104,7 +147,8 @@ impl DefaultWgpuGraphicalAdapterPipeline {
             entries: &[
                 wgpu::BindGroupLayoutEntry {
                     binding: 0,
-                    visibility: wgpu::ShaderStages::VERTEX,
+                    // The fragment shader needs the camera position for specular lighting
+                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
                     ty: wgpu::BindingType::Buffer {
                         ty: wgpu::BufferBindingType::Uniform,
                         has_dynamic_offset: false,
@@ -127,12 +171,51 @@ impl DefaultWgpuGraphicalAdapterPipeline {
             label: Some("Default Pipeline Camera Bind Group"),
         });
 
+        let mut lights_uniform = LightsUniform::new();
+        lights_uniform.update_lights(lights);
+
+        let lights_buffer = device.create_buffer_init(
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("Default Pipeline Lights Buffer"),
+                contents: bytemuck::cast_slice(&[lights_uniform]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            }
+        );
+
+        let lights_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }
+            ],
+            label: Some("Default Pipeline Lights Bind Group Layout"),
+        });
+
+        let lights_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &lights_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: lights_buffer.as_entire_binding(),
+                }
+            ],
+            label: Some("Default Pipeline Lights Bind Group"),
+        });
+
         let render_pipeline_layout =
             device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
                 label: Some("Default Render Pipeline Layout"),
                 bind_group_layouts: &[
                     &camera_bind_group_layout,
                     &texture_bind_group_layout,
+                    &lights_bind_group_layout,
                 ],
                 push_constant_ranges: &[],
             });
@@ -179,7 +262,7 @@ impl DefaultWgpuGraphicalAdapterPipeline {
                 bias: wgpu::DepthBiasState::default(),
             }),
             multisample: wgpu::MultisampleState {
-                count: 1,
+                count: sample_count,
                 mask: !0,
                 alpha_to_coverage_enabled: false,
             },
@@ -190,14 +273,55 @@ impl DefaultWgpuGraphicalAdapterPipeline {
         DefaultWgpuGraphicalAdapterPipeline {
             pipeline: render_pipeline,
             texture_bind_group_layout,
-            models: HashMap::new(),
+            flat_normal_texture,
+            mipmap_generator: None,
+            models: Vec::new(),
             camera_uniform,
             camera_buffer,
             camera_bind_group,
+            lights_uniform,
+            lights_buffer,
+            lights_bind_group,
             model_loader_gateway,
         }
     }
 
+    fn make_material_bind_group_builder(&self) -> MaterialBindGroupBuilder {
+        let flat_normal_texture = self.flat_normal_texture.clone();
+        Box::new(move |
+            device,
+            diffuse_texture,
+            normal_texture,
+            layout
+        | {
+            let normal_texture = normal_texture.unwrap_or(&flat_normal_texture);
+            device.create_bind_group(
+                &wgpu::BindGroupDescriptor {
+                    layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: wgpu::BindingResource::TextureView(&diffuse_texture.view),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: wgpu::BindingResource::Sampler(&diffuse_texture.sampler),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 2,
+                            resource: wgpu::BindingResource::TextureView(&normal_texture.view),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 3,
+                            resource: wgpu::BindingResource::Sampler(&normal_texture.sampler),
+                        },
+                    ],
+                    label: None,
+                }
+            )
+        })
+    }
+
     fn get_model_vertex_desc() -> wgpu::VertexBufferLayout<'static> {
         wgpu::VertexBufferLayout {
             array_stride: size_of::<ModelVertex>() as wgpu::BufferAddress,
@@ -218,6 +342,18 @@ impl DefaultWgpuGraphicalAdapterPipeline {
                     shader_location: 2,
                     format: wgpu::VertexFormat::Float32x3,
                 },
+                // Tangent
+                wgpu::VertexAttribute {
+                    offset: size_of::<[f32; 8]>() as wgpu::BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                // Bitangent
+                wgpu::VertexAttribute {
+                    offset: size_of::<[f32; 11]>() as wgpu::BufferAddress,
+                    shader_location: 4,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
             ],
         }
     }
@@ -254,6 +390,40 @@ impl DefaultWgpuGraphicalAdapterPipeline {
                     shader_location: 8,
                     format: wgpu::VertexFormat::Float32x4,
                 },
+                // The normal matrix, a mat3 taking 3 slots
+                wgpu::VertexAttribute {
+                    offset: size_of::<[f32; 16]>() as wgpu::BufferAddress,
+                    shader_location: 9,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: size_of::<[f32; 19]>() as wgpu::BufferAddress,
+                    shader_location: 10,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: size_of::<[f32; 22]>() as wgpu::BufferAddress,
+                    shader_location: 11,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                // The color multiplier
+                wgpu::VertexAttribute {
+                    offset: size_of::<[f32; 25]>() as wgpu::BufferAddress,
+                    shader_location: 12,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                // The emissive color
+                wgpu::VertexAttribute {
+                    offset: size_of::<[f32; 29]>() as wgpu::BufferAddress,
+                    shader_location: 13,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                // The user defined id
+                wgpu::VertexAttribute {
+                    offset: size_of::<[f32; 32]>() as wgpu::BufferAddress,
+                    shader_location: 14,
+                    format: wgpu::VertexFormat::Uint32,
+                },
             ],
         }
     }
@@ -268,61 +438,104 @@ impl WgpuGraphicalAdapterPipeline for DefaultWgpuGraphicalAdapterPipeline {
         device: &wgpu::Device,
         queue: &wgpu::Queue,
     ) -> anyhow::Result<()> {
+        if self.has_model(id) {
+            return Err(WgpuGraphicalAdapterError::ModelAlreadyLoaded(id.to_string()).into());
+        }
+        let bind_group_builder = self.make_material_bind_group_builder();
+        let mipmap_generator = self.mipmap_generator.get_or_insert_with(|| MipmapGenerator::new(device));
         let model = self.model_loader_gateway.load_model_sync(
             filename,
             instances,
             device,
             queue,
             &self.texture_bind_group_layout,
-            Box::new(|
-                device,
-                texture_view,
-                sampler,
-                layout
-            | {
-                device.create_bind_group(
-                    &wgpu::BindGroupDescriptor {
-                        layout,
-                        entries: &[
-                            wgpu::BindGroupEntry {
-                                binding: 0,
-                                resource: wgpu::BindingResource::TextureView(&texture_view),
-                            },
-                            wgpu::BindGroupEntry {
-                                binding: 1,
-                                resource: wgpu::BindingResource::Sampler(&sampler),
-                            }
-                        ],
-                        label: None,
-                    }
-                )
-            }),
+            bind_group_builder,
+            mipmap_generator,
         )?;
-        self.models.insert(id.to_string(), model);
+        self.models.push(OrderedModel {
+            id: id.to_string(),
+            order: 0,
+            model,
+        });
+        self.models.sort_by_key(|ordered_model| ordered_model.order);
+        Ok(())
+    }
+
+    fn unload_model(&mut self, id: &str) -> anyhow::Result<()> {
+        let index = self.models.iter()
+            .position(|ordered_model| ordered_model.id == id)
+            .ok_or_else(|| WgpuGraphicalAdapterError::ModelNotFound(id.to_string()))?;
+        // Removing keeps the order of the other models
+        let ordered_model = self.models.remove(index);
+        ordered_model.model.destroy();
         Ok(())
     }
 
-    fn update_camera(&mut self, camera: &PerspectiveCamera, queue: &wgpu::Queue) {
+    fn has_model(&self, id: &str) -> bool {
+        self.models.iter().any(|ordered_model| ordered_model.id == id)
+    }
+
+    fn update_camera(&mut self, camera: &dyn Camera, queue: &wgpu::Queue) {
         self.camera_uniform.update_view_proj(camera);
         queue.write_buffer(&self.camera_buffer, 0, bytemuck::cast_slice(&[self.camera_uniform]));
     }
 
+    fn update_lights(&mut self, lights: &[Light], queue: &wgpu::Queue) {
+        self.lights_uniform.update_lights(lights);
+        queue.write_buffer(&self.lights_buffer, 0, bytemuck::cast_slice(&[self.lights_uniform]));
+    }
+
     fn render<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
         render_pass.set_pipeline(&self.pipeline);
-        for (id, model) in &self.models {
-
-            log::debug!("Drawing model: {}", id);
+        render_pass.set_bind_group(2, &self.lights_bind_group, &[]);
+        for ordered_model in &self.models {
+            log::debug!("Drawing model: {}", ordered_model.id);
             use crate::model::DrawModel;
-            render_pass.draw_model_instanced(model, &self.camera_bind_group, None);
+            render_pass.draw_model_instanced(&ordered_model.model, &self.camera_bind_group, None);
         }
     }
     
-    fn update_model_instances(&mut self, model_id: &str, instances: Vec<Instance>, device: &wgpu::Device) -> anyhow::Result<()> {
-        if let Some(model) = self.models.get_mut(model_id) {
-            model.instances = Instance::instances_to_buffer(&instances, device);
+    fn update_model_instances(
+        &mut self,
+        model_id: &str,
+        instances: Vec<Instance>,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> anyhow::Result<()> {
+        if let Some(ordered_model) = self.models.iter_mut().find(|ordered_model| ordered_model.id == model_id) {
+            ordered_model.model.instances.update(&instances, device, queue);
+            Ok(())
+        } else {
+            Err(WgpuGraphicalAdapterError::ModelNotFound(model_id.to_string()).into())
+        }
+    }
+
+    fn update_model_instances_range(
+        &mut self,
+        model_id: &str,
+        first_instance: u32,
+        instances: Vec<Instance>,
+        queue: &wgpu::Queue,
+    ) -> anyhow::Result<()> {
+        if let Some(ordered_model) = self.models.iter_mut().find(|ordered_model| ordered_model.id == model_id) {
+            ordered_model.model.instances.update_range(first_instance, &instances, queue)
+        } else {
+            Err(WgpuGraphicalAdapterError::ModelNotFound(model_id.to_string()).into())
+        }
+    }
+
+    fn list_models(&self) -> Vec<&str> {
+        self.models.iter().map(|ordered_model| ordered_model.id.as_str()).collect()
+    }
+
+    fn set_model_order(&mut self, model_id: &str, order: i32) -> anyhow::Result<()> {
+        if let Some(ordered_model) = self.models.iter_mut().find(|ordered_model| ordered_model.id == model_id) {
+            ordered_model.order = order;
+            // Stable, so models with the same order stay in their loading order
+            self.models.sort_by_key(|ordered_model| ordered_model.order);
             Ok(())
         } else {
-            Err(anyhow::anyhow!("Model not found: {}", model_id))
+            Err(WgpuGraphicalAdapterError::ModelNotFound(model_id.to_string()).into())
         }
     }
 }
\ No newline at end of file
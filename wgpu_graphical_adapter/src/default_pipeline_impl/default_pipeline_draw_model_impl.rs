@@ -30,8 +30,8 @@ where
             self.draw_mesh_instanced(
                 mesh,
                 material,
-                &model.instances,
-                instances.clone().unwrap_or(0..model.num_instances),
+                &model.instances.buffer,
+                instances.clone().unwrap_or(0..model.instances.len()),
                 camera_bind_group
             );
         }
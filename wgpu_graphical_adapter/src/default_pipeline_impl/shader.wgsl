@@ -1,5 +1,6 @@
 // Vertex shader
 struct CameraUniform {
+    view_position: vec4<f32>,
     view_proj: mat4x4<f32>,
 }
 @group(0) @binding(0)
@@ -8,6 +9,9 @@ var<uniform> camera: CameraUniform;
 struct VertexInput {
     @location(0) position: vec3<f32>,
     @location(1) tex_coords: vec2<f32>,
+    @location(2) normal: vec3<f32>,
+    @location(3) tangent: vec3<f32>,
+    @location(4) bitangent: vec3<f32>,
 }
 
 struct InstanceInput {
@@ -15,11 +19,25 @@ struct InstanceInput {
     @location(6) model_matrix_1: vec4<f32>,
     @location(7) model_matrix_2: vec4<f32>,
     @location(8) model_matrix_3: vec4<f32>,
+    @location(9) normal_matrix_0: vec3<f32>,
+    @location(10) normal_matrix_1: vec3<f32>,
+    @location(11) normal_matrix_2: vec3<f32>,
+    @location(12) color: vec4<f32>,
+    @location(13) emissive: vec3<f32>,
+    @location(14) id: u32,
 }
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) tex_coords: vec2<f32>,
+    @location(1) world_normal: vec3<f32>,
+    @location(2) world_position: vec3<f32>,
+    @location(3) world_tangent: vec3<f32>,
+    @location(4) world_bitangent: vec3<f32>,
+    @location(5) color: vec4<f32>,
+    @location(6) emissive: vec3<f32>,
+    // Unused by this shader, kept for shaders built on top of it
+    @location(7) @interpolate(flat) instance_id: u32,
 }
 
 @vertex
@@ -34,9 +52,26 @@ fn vs_main(
         instance.model_matrix_3,
     );
 
+    let normal_matrix = mat3x3<f32>(
+        instance.normal_matrix_0,
+        instance.normal_matrix_1,
+        instance.normal_matrix_2,
+    );
+
+    let world_position = model_matrix * vec4<f32>(model.position, 1.0);
+
     var out: VertexOutput;
     out.tex_coords = model.tex_coords;
-    out.clip_position = camera.view_proj * model_matrix * vec4<f32>(model.position, 1.0);
+    // The tangents lie on the surface and follow the model matrix, the normals need the normal matrix
+    // to stay perpendicular to it when the instance is scaled non-uniformly
+    out.world_normal = normal_matrix * model.normal;
+    out.world_tangent = (model_matrix * vec4<f32>(model.tangent, 0.0)).xyz;
+    out.world_bitangent = (model_matrix * vec4<f32>(model.bitangent, 0.0)).xyz;
+    out.world_position = world_position.xyz;
+    out.clip_position = camera.view_proj * world_position;
+    out.color = instance.color;
+    out.emissive = instance.emissive;
+    out.instance_id = instance.id;
     return out;
 }
 
@@ -46,9 +81,95 @@ fn vs_main(
 var t_diffuse: texture_2d<f32>;
 @group(1) @binding(1)
 var s_diffuse: sampler;
+@group(1) @binding(2)
+var t_normal: texture_2d<f32>;
+@group(1) @binding(3)
+var s_normal: sampler;
+
+// Must match MAX_LIGHTS in light.rs
+const MAX_LIGHTS: u32 = 16u;
+
+const LIGHT_KIND_DIRECTIONAL: u32 = 0u;
+const LIGHT_KIND_POINT: u32 = 1u;
+const LIGHT_KIND_SPOT: u32 = 2u;
+
+const AMBIENT_STRENGTH: f32 = 0.1;
+const SPECULAR_STRENGTH: f32 = 0.5;
+const SHININESS: f32 = 32.0;
+
+struct Light {
+    position: vec3<f32>,
+    kind: u32,
+    direction: vec3<f32>,
+    range: f32,
+    color: vec3<f32>,
+    intensity: f32,
+    inner_cone_cos: f32,
+    outer_cone_cos: f32,
+}
+
+struct LightsUniform {
+    count: u32,
+    lights: array<Light, MAX_LIGHTS>,
+}
+@group(2) @binding(0)
+var<uniform> lights: LightsUniform;
+
+// Blinn-Phong diffuse and specular terms of a single light
+fn light_contribution(light: Light, world_position: vec3<f32>, normal: vec3<f32>, view_dir: vec3<f32>) -> vec3<f32> {
+    var light_dir: vec3<f32>;
+    var attenuation = 1.0;
+    if (light.kind == LIGHT_KIND_DIRECTIONAL) {
+        light_dir = -light.direction;
+    } else {
+        let to_light = light.position - world_position;
+        let distance = length(to_light);
+        light_dir = to_light / distance;
+        // Smoothly reaches zero at range
+        let falloff = clamp(1.0 - pow(distance / light.range, 4.0), 0.0, 1.0);
+        attenuation = falloff * falloff / (distance * distance + 1.0);
+        if (light.kind == LIGHT_KIND_SPOT) {
+            let cos_angle = dot(-light_dir, light.direction);
+            attenuation *= smoothstep(light.outer_cone_cos, light.inner_cone_cos, cos_angle);
+        }
+    }
+
+    let diffuse = max(dot(normal, light_dir), 0.0);
+    let half_dir = normalize(view_dir + light_dir);
+    let specular = pow(max(dot(normal, half_dir), 0.0), SHININESS) * SPECULAR_STRENGTH;
+
+    return (diffuse + specular) * light.color * light.intensity * attenuation;
+}
+
+// Moves the normal sampled from the normal map from tangent space to world space
+fn perturbed_normal(in: VertexOutput) -> vec3<f32> {
+    // Sampled before branching, textureSample must be called from uniform control flow
+    let tangent_space_normal = textureSample(t_normal, s_normal, in.tex_coords).xyz * 2.0 - 1.0;
+    let geometric_normal = normalize(in.world_normal);
+    // Vertices without texture coordinates have no tangent space
+    if (dot(in.world_tangent, in.world_tangent) == 0.0) {
+        return geometric_normal;
+    }
+    let tangent_matrix = mat3x3<f32>(
+        normalize(in.world_tangent),
+        normalize(in.world_bitangent),
+        geometric_normal,
+    );
+    return normalize(tangent_matrix * tangent_space_normal);
+}
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return textureSample(t_diffuse, s_diffuse, in.tex_coords);
+    let object_color = textureSample(t_diffuse, s_diffuse, in.tex_coords) * in.color;
+
+    let normal = perturbed_normal(in);
+    let view_dir = normalize(camera.view_position.xyz - in.world_position);
+
+    var lighting = vec3<f32>(AMBIENT_STRENGTH);
+    let count = min(lights.count, MAX_LIGHTS);
+    for (var i = 0u; i < count; i++) {
+        lighting += light_contribution(lights.lights[i], in.world_position, normal, view_dir);
+    }
+
+    return vec4<f32>(lighting * object_color.rgb + in.emissive, object_color.a);
 }
- 
\ No newline at end of file
@@ -0,0 +1,98 @@
+const WORKGROUP_SIZE: u32 = 16;
+
+// Renders an equirectangular image (e.g. an HDR environment) into the six layers of a cubemap with a compute shader.
+// The source must be an Rgba32Float texture, the destination an Rgba16Float texture with 6 array layers
+// and the STORAGE_BINDING usage.
+pub fn equirectangular_to_cubemap(
+    source: &wgpu::Texture,
+    destination: &wgpu::Texture,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+) {
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Equirectangular To Cubemap Shader"),
+        source: wgpu::ShaderSource::Wgsl(include_str!("equirectangular.wgsl").into()),
+    });
+
+    let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    // 32 bit float textures are only filterable with Features::FLOAT32_FILTERABLE
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::StorageTexture {
+                    access: wgpu::StorageTextureAccess::WriteOnly,
+                    format: destination.format(),
+                    view_dimension: wgpu::TextureViewDimension::D2Array,
+                },
+                count: None,
+            },
+        ],
+        label: Some("Equirectangular To Cubemap Bind Group Layout"),
+    });
+
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Equirectangular To Cubemap Pipeline Layout"),
+        bind_group_layouts: &[&bind_group_layout],
+        push_constant_ranges: &[],
+    });
+
+    let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some("Equirectangular To Cubemap Pipeline"),
+        layout: Some(&pipeline_layout),
+        module: &shader,
+        entry_point: "cs_main",
+        compilation_options: wgpu::PipelineCompilationOptions::default(),
+        cache: None,
+    });
+
+    let source_view = source.create_view(&wgpu::TextureViewDescriptor::default());
+    let destination_view = destination.create_view(&wgpu::TextureViewDescriptor {
+        label: Some("Equirectangular To Cubemap Destination View"),
+        dimension: Some(wgpu::TextureViewDimension::D2Array),
+        ..Default::default()
+    });
+
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout: &bind_group_layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&source_view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::TextureView(&destination_view),
+            },
+        ],
+        label: Some("Equirectangular To Cubemap Bind Group"),
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Equirectangular To Cubemap Encoder"),
+    });
+    {
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Equirectangular To Cubemap Pass"),
+            timestamp_writes: None,
+        });
+        compute_pass.set_pipeline(&pipeline);
+        compute_pass.set_bind_group(0, &bind_group, &[]);
+        compute_pass.dispatch_workgroups(
+            destination.width().div_ceil(WORKGROUP_SIZE),
+            destination.height().div_ceil(WORKGROUP_SIZE),
+            6,
+        );
+    }
+    queue.submit(std::iter::once(encoder.finish()));
+}
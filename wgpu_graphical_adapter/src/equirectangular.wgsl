@@ -0,0 +1,50 @@
+// Projects an equirectangular (latitude/longitude) image on the six faces of a cubemap
+const PI: f32 = 3.14159265358979;
+
+// Directions of a face texel, following the cubemap face layout of the WebGPU specification
+struct Face {
+    forward: vec3<f32>,
+    right: vec3<f32>,
+    down: vec3<f32>,
+}
+
+@group(0) @binding(0)
+var t_source: texture_2d<f32>;
+@group(0) @binding(1)
+var t_destination: texture_storage_2d_array<rgba16float, write>;
+
+@compute
+@workgroup_size(16, 16, 1)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let face_size = textureDimensions(t_destination);
+    if (id.x >= face_size.x || id.y >= face_size.y) {
+        return;
+    }
+
+    // +X, -X, +Y, -Y, +Z, -Z
+    var faces = array<Face, 6>(
+        Face(vec3<f32>(1.0, 0.0, 0.0), vec3<f32>(0.0, 0.0, -1.0), vec3<f32>(0.0, -1.0, 0.0)),
+        Face(vec3<f32>(-1.0, 0.0, 0.0), vec3<f32>(0.0, 0.0, 1.0), vec3<f32>(0.0, -1.0, 0.0)),
+        Face(vec3<f32>(0.0, 1.0, 0.0), vec3<f32>(1.0, 0.0, 0.0), vec3<f32>(0.0, 0.0, 1.0)),
+        Face(vec3<f32>(0.0, -1.0, 0.0), vec3<f32>(1.0, 0.0, 0.0), vec3<f32>(0.0, 0.0, -1.0)),
+        Face(vec3<f32>(0.0, 0.0, 1.0), vec3<f32>(1.0, 0.0, 0.0), vec3<f32>(0.0, -1.0, 0.0)),
+        Face(vec3<f32>(0.0, 0.0, -1.0), vec3<f32>(-1.0, 0.0, 0.0), vec3<f32>(0.0, -1.0, 0.0)),
+    );
+    let face = faces[id.z];
+
+    // From -1 to 1 across the face, through the center of the texel
+    let face_coords = (vec2<f32>(id.xy) + 0.5) / vec2<f32>(face_size) * 2.0 - 1.0;
+    let direction = normalize(face.forward + face.right * face_coords.x + face.down * face_coords.y);
+
+    // Longitude along x, latitude along y with the top row looking straight up
+    let source_coords = vec2<f32>(
+        atan2(direction.z, direction.x) / (2.0 * PI) + 0.5,
+        0.5 - asin(direction.y) / PI,
+    );
+    let source_size = textureDimensions(t_source);
+    // The source is a float texture, which can't be filtered on every adapter
+    let texel = min(vec2<u32>(source_coords * vec2<f32>(source_size)), source_size - 1u);
+    let color = textureLoad(t_source, texel, 0);
+
+    textureStore(t_destination, id.xy, id.z, vec4<f32>(color.rgb, 1.0));
+}
@@ -0,0 +1,34 @@
+use std::fmt;
+
+// Errors the caller may want to react to, they are returned wrapped in anyhow::Error
+// and can be recovered with `error.downcast_ref::<WgpuGraphicalAdapterError>()`.
+#[derive(Debug)]
+pub enum WgpuGraphicalAdapterError {
+    // The surface could not allocate a new frame, the application can't recover from this.
+    SurfaceOutOfMemory,
+    PipelineNotFound(String),
+    ModelNotFound(String),
+    // Models must be unloaded before loading another one with the same id.
+    ModelAlreadyLoaded(String),
+}
+
+impl fmt::Display for WgpuGraphicalAdapterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WgpuGraphicalAdapterError::SurfaceOutOfMemory => {
+                write!(f, "Out of memory while acquiring the next surface texture")
+            }
+            WgpuGraphicalAdapterError::PipelineNotFound(pipeline_id) => {
+                write!(f, "Pipeline not found: {}", pipeline_id)
+            }
+            WgpuGraphicalAdapterError::ModelNotFound(model_id) => {
+                write!(f, "Model not found: {}", model_id)
+            }
+            WgpuGraphicalAdapterError::ModelAlreadyLoaded(model_id) => {
+                write!(f, "Model already loaded: {}", model_id)
+            }
+        }
+    }
+}
+
+impl std::error::Error for WgpuGraphicalAdapterError {}
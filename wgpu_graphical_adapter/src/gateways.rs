@@ -1,7 +1,18 @@
 use crate::instance::Instance;
+use crate::mipmap::MipmapGenerator;
 use crate::model::Model;
+use crate::texture::Texture;
+
+// Builds the bind group of a material from its diffuse texture and, when it has one, its normal map.
+pub type MaterialBindGroupBuilder = Box<dyn Fn(
+    &wgpu::Device,
+    &Texture,
+    Option<&Texture>,
+    &wgpu::BindGroupLayout
+) -> wgpu::BindGroup>;
 
 pub trait WgpuModelLoaderGateway {
+    #[allow(clippy::too_many_arguments)]
     fn load_model_sync(
         &self,
         file_name: &str,
@@ -9,11 +20,19 @@ pub trait WgpuModelLoaderGateway {
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         texture_bind_group_layout: &wgpu::BindGroupLayout,
-        bind_group_builder: Box<dyn Fn(
-            &wgpu::Device,
-            &wgpu::TextureView,
-            &wgpu::Sampler,
-            &wgpu::BindGroupLayout
-        ) -> wgpu::BindGroup>,
+        bind_group_builder: MaterialBindGroupBuilder,
+        // Shared by the textures of the models of a pipeline
+        mipmap_generator: &mut MipmapGenerator,
     ) -> anyhow::Result<Model>;
-}
\ No newline at end of file
+}
+
+pub trait WgpuCubemapLoaderGateway {
+    // Loads an equirectangular image when the file name has the .hdr extension,
+    // otherwise six face images named by replacing {} in the file name with px, nx, py, ny, pz and nz.
+    fn load_cubemap_sync(
+        &self,
+        file_name: &str,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> anyhow::Result<Texture>;
+}
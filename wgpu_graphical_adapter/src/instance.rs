@@ -1,32 +1,178 @@
-use wgpu::util::DeviceExt;
+use cgmath::SquareMatrix;
 
-#[derive(Clone)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Instance {
-    pub position: cgmath::Vector3<f32>,
-    pub rotation: cgmath::Quaternion<f32>,
+    // Transforms the model from its own space to the world space.
+    pub model: cgmath::Matrix4<f32>,
+    // Multiplies the color of the model, in linear RGBA. White leaves it as it is.
+    pub color: [f32; 4],
+    // Added to the lit color of the model, in linear RGB, so the instance glows even in the dark.
+    pub emissive: [f32; 3],
+    // Not used for drawing, available to custom shaders, for example to pick instances.
+    pub id: u32,
 }
 
 impl Instance {
-    pub fn instances_to_buffer(instances: &[Instance], device: &wgpu::Device) -> wgpu::Buffer {
-        let instance_data = instances.iter().map(Instance::to_raw).collect::<Vec<_>>();
-         device.create_buffer_init(
-            &wgpu::util::BufferInitDescriptor {
-                label: None,
-                contents: bytemuck::cast_slice(&instance_data),
-                usage: wgpu::BufferUsages::VERTEX,
-            }
+    pub fn new(position: cgmath::Vector3<f32>, rotation: cgmath::Quaternion<f32>) -> Self {
+        Self::new_uniformly_scaled(position, rotation, 1.0)
+    }
+
+    pub fn new_uniformly_scaled(
+        position: cgmath::Vector3<f32>,
+        rotation: cgmath::Quaternion<f32>,
+        scale: f32,
+    ) -> Self {
+        Self::new_scaled(position, rotation, cgmath::Vector3::new(scale, scale, scale))
+    }
+
+    // Scaled first, then rotated, then translated.
+    pub fn new_scaled(
+        position: cgmath::Vector3<f32>,
+        rotation: cgmath::Quaternion<f32>,
+        scale: cgmath::Vector3<f32>,
+    ) -> Self {
+        Self::from_matrix(
+            cgmath::Matrix4::from_translation(position)
+                * cgmath::Matrix4::from(rotation)
+                * cgmath::Matrix4::from_nonuniform_scale(scale.x, scale.y, scale.z)
         )
-    } 
-    
+    }
+
+    // Any affine transform, including shearing.
+    pub fn from_matrix(model: cgmath::Matrix4<f32>) -> Self {
+        Instance {
+            model,
+            color: [1.0; 4],
+            emissive: [0.0; 3],
+            id: 0,
+        }
+    }
+
+    pub fn with_color(self, color: [f32; 4]) -> Self {
+        Instance { color, ..self }
+    }
+
+    pub fn with_emissive(self, emissive: [f32; 3]) -> Self {
+        Instance { emissive, ..self }
+    }
+
+    pub fn with_id(self, id: u32) -> Self {
+        Instance { id, ..self }
+    }
+
     pub fn to_raw(&self) -> InstanceRaw {
         InstanceRaw {
-            model: (cgmath::Matrix4::from_translation(self.position) * cgmath::Matrix4::from(self.rotation)).into(),
+            model: self.model.into(),
+            normal: self.normal_matrix().into(),
+            color: self.color,
+            emissive: self.emissive,
+            id: self.id,
         }
     }
+
+    // The inverse-transpose of the model matrix without its translation, which keeps the normals
+    // perpendicular to the surface under non-uniform scaling.
+    fn normal_matrix(&self) -> cgmath::Matrix3<f32> {
+        use cgmath::Matrix;
+        let model = cgmath::Matrix3::from_cols(
+            self.model.x.truncate(),
+            self.model.y.truncate(),
+            self.model.z.truncate(),
+        );
+        // A scale of zero flattens the model, its normals are left as they are.
+        model.invert().map(|inverse| inverse.transpose()).unwrap_or(model)
+    }
 }
 
 #[repr(C)]
 #[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct InstanceRaw {
     model: [[f32; 4]; 4],
-}
\ No newline at end of file
+    normal: [[f32; 3]; 3],
+    color: [f32; 4],
+    emissive: [f32; 3],
+    id: u32,
+}
+
+// The instances of a model, on the GPU. The buffer is reused and only reallocated when it has to grow.
+pub struct InstanceBuffer {
+    pub buffer: wgpu::Buffer,
+    len: u32,
+    capacity: u32,
+}
+
+impl InstanceBuffer {
+    pub fn new(instances: &[Instance], device: &wgpu::Device, queue: &wgpu::Queue) -> InstanceBuffer {
+        let len = instances.len() as u32;
+        // Never empty, wgpu doesn't allow binding empty buffers
+        let capacity = len.max(1);
+        let instance_buffer = InstanceBuffer {
+            buffer: Self::create_buffer(capacity, device),
+            len,
+            capacity,
+        };
+        instance_buffer.write(0, instances, queue);
+        instance_buffer
+    }
+
+    pub fn len(&self) -> u32 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Replaces every instance, the count can change.
+    pub fn update(&mut self, instances: &[Instance], device: &wgpu::Device, queue: &wgpu::Queue) {
+        let len = instances.len() as u32;
+        if len > self.capacity {
+            // Grows geometrically, so a slowly growing crowd doesn't reallocate every frame
+            let capacity = len.next_power_of_two();
+            log::debug!("Growing instance buffer from {} to {} instances", self.capacity, capacity);
+            self.buffer.destroy();
+            self.buffer = Self::create_buffer(capacity, device);
+            self.capacity = capacity;
+        }
+        self.len = len;
+        self.write(0, instances, queue);
+    }
+
+    // Replaces the instances from first_instance on, without changing the count.
+    pub fn update_range(&mut self, first_instance: u32, instances: &[Instance], queue: &wgpu::Queue) -> anyhow::Result<()> {
+        let end = first_instance as usize + instances.len();
+        if end > self.len as usize {
+            return Err(anyhow::anyhow!(
+                "Instance range {}..{} out of bounds, the model has {} instances",
+                first_instance, end, self.len,
+            ));
+        }
+        self.write(first_instance, instances, queue);
+        Ok(())
+    }
+
+    pub fn destroy(&self) {
+        self.buffer.destroy();
+    }
+
+    fn write(&self, first_instance: u32, instances: &[Instance], queue: &wgpu::Queue) {
+        if instances.is_empty() {
+            return;
+        }
+        let instance_data = instances.iter().map(Instance::to_raw).collect::<Vec<_>>();
+        queue.write_buffer(
+            &self.buffer,
+            first_instance as wgpu::BufferAddress * size_of::<InstanceRaw>() as wgpu::BufferAddress,
+            bytemuck::cast_slice(&instance_data),
+        );
+    }
+
+    fn create_buffer(capacity: u32, device: &wgpu::Device) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Instance Buffer"),
+            size: capacity as wgpu::BufferAddress * size_of::<InstanceRaw>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+}
@@ -1,9 +1,17 @@
 pub mod state;
+pub mod config;
 pub mod pipeline;
+pub mod background;
 pub mod default_pipeline_impl;
+pub mod skybox_pipeline_impl;
 pub mod texture;
+pub mod mipmap;
+pub mod equirectangular;
 pub mod model;
 pub mod vertex;
 pub mod camera;
+pub mod camera_controller;
+pub mod light;
 pub mod instance;
 pub mod gateways;
+pub mod errors;
@@ -0,0 +1,116 @@
+use cgmath::InnerSpace;
+
+// Must match MAX_LIGHTS in the shaders.
+pub const MAX_LIGHTS: usize = 16;
+
+pub const LIGHT_KIND_DIRECTIONAL: u32 = 0;
+pub const LIGHT_KIND_POINT: u32 = 1;
+pub const LIGHT_KIND_SPOT: u32 = 2;
+
+#[derive(Clone, Debug)]
+pub enum LightKind {
+    // Lights the whole scene from the same direction, like the sun.
+    Directional {
+        direction: cgmath::Vector3<f32>,
+    },
+    // Lights in every direction from a position, fading out until range.
+    Point {
+        position: cgmath::Point3<f32>,
+        range: f32,
+    },
+    // Lights a cone from a position, fading out between the inner and the outer cone angles.
+    Spot {
+        position: cgmath::Point3<f32>,
+        direction: cgmath::Vector3<f32>,
+        range: f32,
+        inner_cone_angle: cgmath::Deg<f32>,
+        outer_cone_angle: cgmath::Deg<f32>,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct Light {
+    pub kind: LightKind,
+    pub color: [f32; 3], // Linear RGB.
+    pub intensity: f32,
+}
+
+impl Light {
+    pub fn to_raw(&self) -> LightRaw {
+        let mut raw = LightRaw {
+            position: [0.0; 3],
+            kind: LIGHT_KIND_DIRECTIONAL,
+            direction: [0.0, -1.0, 0.0],
+            range: 0.0,
+            color: self.color,
+            intensity: self.intensity,
+            inner_cone_cos: 0.0,
+            outer_cone_cos: 0.0,
+            _padding: [0.0; 2],
+        };
+        match &self.kind {
+            LightKind::Directional { direction } => {
+                raw.direction = direction.normalize().into();
+            }
+            LightKind::Point { position, range } => {
+                raw.kind = LIGHT_KIND_POINT;
+                raw.position = (*position).into();
+                raw.range = *range;
+            }
+            LightKind::Spot { position, direction, range, inner_cone_angle, outer_cone_angle } => {
+                use cgmath::Angle;
+                raw.kind = LIGHT_KIND_SPOT;
+                raw.position = (*position).into();
+                raw.direction = direction.normalize().into();
+                raw.range = *range;
+                raw.inner_cone_cos = inner_cone_angle.cos();
+                raw.outer_cone_cos = outer_cone_angle.cos();
+            }
+        }
+        raw
+    }
+}
+
+// Laid out so that every field respects the WGSL uniform alignment rules (vec3 are 16 bytes aligned).
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct LightRaw {
+    position: [f32; 3],
+    kind: u32,
+    direction: [f32; 3],
+    range: f32,
+    color: [f32; 3],
+    intensity: f32,
+    inner_cone_cos: f32,
+    outer_cone_cos: f32,
+    _padding: [f32; 2],
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct LightsUniform {
+    count: u32,
+    _padding: [u32; 3],
+    lights: [LightRaw; MAX_LIGHTS],
+}
+
+// No light
+impl Default for LightsUniform {
+    fn default() -> Self {
+        bytemuck::Zeroable::zeroed()
+    }
+}
+
+impl LightsUniform {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update_lights(&mut self, lights: &[Light]) {
+        let count = lights.len().min(MAX_LIGHTS);
+        for (raw, light) in self.lights.iter_mut().zip(lights.iter()) {
+            *raw = light.to_raw();
+        }
+        self.count = count as u32;
+    }
+}
@@ -1,15 +1,35 @@
+use crate::instance::InstanceBuffer;
 use crate::texture::Texture;
 
 pub struct Model {
     pub meshes: Vec<Mesh>,
     pub materials: Vec<Material>,
-    pub instances: wgpu::Buffer,
-    pub num_instances: u32,
+    pub instances: InstanceBuffer,
+}
+
+impl Model {
+    // Frees the GPU memory right away instead of when the last reference is dropped,
+    // the model must not be drawn anymore.
+    pub fn destroy(&self) {
+        for mesh in &self.meshes {
+            mesh.vertex_buffer.destroy();
+            mesh.index_buffer.destroy();
+        }
+        for material in &self.materials {
+            material.diffuse_texture.destroy();
+            if let Some(normal_texture) = &material.normal_texture {
+                normal_texture.destroy();
+            }
+        }
+        self.instances.destroy();
+    }
 }
 
 pub struct Material {
     pub name: String,
     pub diffuse_texture: Texture,
+    // Materials without a normal map are drawn with a flat one.
+    pub normal_texture: Option<Texture>,
     pub bind_group: wgpu::BindGroup,
 }
 
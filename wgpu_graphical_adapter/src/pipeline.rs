@@ -1,12 +1,38 @@
-use crate::camera::PerspectiveCamera;
+use crate::camera::Camera;
 use crate::instance::Instance;
+use crate::light::Light;
+
+// A pipeline given to the state constructors. Pipelines are drawn by ascending order,
+// those with the same order are drawn in their registration order.
+pub struct PipelineRegistration {
+    pub id: String,
+    pub order: i32,
+    pub factory: Box<dyn WgpuGraphicalAdapterPipelineFactory>,
+}
+
+impl PipelineRegistration {
+    pub fn new(
+        id: &str,
+        order: i32,
+        factory: Box<dyn WgpuGraphicalAdapterPipelineFactory>,
+    ) -> PipelineRegistration {
+        PipelineRegistration {
+            id: id.to_string(),
+            order,
+            factory,
+        }
+    }
+}
 
 pub trait WgpuGraphicalAdapterPipelineFactory {
     fn create(
         &self,
         device: &wgpu::Device,
+        queue: &wgpu::Queue,
         format: wgpu::TextureFormat,
-        camera: &PerspectiveCamera,
+        sample_count: u32,
+        camera: &dyn Camera,
+        lights: &[Light],
     ) -> Box<dyn WgpuGraphicalAdapterPipeline>;
 }
 
@@ -18,7 +44,31 @@ pub trait WgpuGraphicalAdapterPipeline {
                        device: &wgpu::Device,
                        queue: &wgpu::Queue,
     ) -> anyhow::Result<()>;
-    fn update_camera(&mut self, camera: &PerspectiveCamera, queue: &wgpu::Queue);
+    fn update_camera(&mut self, camera: &dyn Camera, queue: &wgpu::Queue);
+    fn update_lights(&mut self, lights: &[Light], queue: &wgpu::Queue);
     fn render<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>);
-    fn update_model_instances(&mut self, model_id: &str, instances: Vec<Instance>, device: &wgpu::Device) -> anyhow::Result<()>;
+    // Destroys the model buffers and textures
+    fn unload_model(&mut self, id: &str) -> anyhow::Result<()>;
+    fn has_model(&self, id: &str) -> bool;
+    // Replaces every instance of the model, the instance count can change.
+    fn update_model_instances(
+        &mut self,
+        model_id: &str,
+        instances: Vec<Instance>,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> anyhow::Result<()>;
+    // Replaces the instances starting at first_instance, the instance count stays the same.
+    fn update_model_instances_range(
+        &mut self,
+        model_id: &str,
+        first_instance: u32,
+        instances: Vec<Instance>,
+        queue: &wgpu::Queue,
+    ) -> anyhow::Result<()>;
+    // Model ids in draw order
+    fn list_models(&self) -> Vec<&str>;
+    // Models are drawn by ascending order, those with the same order are drawn in their loading order.
+    // Loaded models have the order 0.
+    fn set_model_order(&mut self, model_id: &str, order: i32) -> anyhow::Result<()>;
 }
\ No newline at end of file
@@ -0,0 +1 @@
+pub mod skybox_pipeline;
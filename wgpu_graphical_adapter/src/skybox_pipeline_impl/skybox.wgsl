@@ -0,0 +1,40 @@
+// Vertex shader
+struct SkyboxUniform {
+    inverse_projection: mat4x4<f32>,
+    // The view matrix without its translation, so the skybox follows the camera
+    inverse_view_rotation: mat4x4<f32>,
+}
+@group(0) @binding(0)
+var<uniform> skybox: SkyboxUniform;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) direction: vec3<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    // A single triangle covering the whole render target, on the far plane
+    let tex_coords = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    let clip_position = vec4<f32>(tex_coords * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 1.0, 1.0);
+
+    let view_position = skybox.inverse_projection * clip_position;
+
+    var out: VertexOutput;
+    out.clip_position = clip_position;
+    out.direction = (skybox.inverse_view_rotation * vec4<f32>(view_position.xyz / view_position.w, 0.0)).xyz;
+    return out;
+}
+
+// Fragment shader
+
+@group(1) @binding(0)
+var t_environment: texture_cube<f32>;
+@group(1) @binding(1)
+var s_environment: sampler;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(t_environment, s_environment, normalize(in.direction));
+    return vec4<f32>(color.rgb, 1.0);
+}
@@ -0,0 +1,320 @@
+use std::rc::Rc;
+
+use cgmath::{Matrix, SquareMatrix};
+use wgpu::util::DeviceExt;
+
+use crate::camera::Camera;
+use crate::errors::WgpuGraphicalAdapterError;
+use crate::gateways::WgpuCubemapLoaderGateway;
+use crate::instance::Instance;
+use crate::light::Light;
+use crate::pipeline::{WgpuGraphicalAdapterPipeline, WgpuGraphicalAdapterPipelineFactory};
+use crate::texture::Texture;
+
+// Draws a cubemap environment behind everything else. It can be registered alongside the default pipeline,
+// the environment is loaded with load_model_sync, any instances are ignored.
+pub struct SkyboxWgpuGraphicalAdapterPipelineFactory {
+    cubemap_loader_gateway: Rc<dyn WgpuCubemapLoaderGateway>,
+}
+
+impl SkyboxWgpuGraphicalAdapterPipelineFactory {
+    pub fn new(
+        cubemap_loader_gateway: Rc<dyn WgpuCubemapLoaderGateway>
+    ) -> SkyboxWgpuGraphicalAdapterPipelineFactory {
+        SkyboxWgpuGraphicalAdapterPipelineFactory {
+            cubemap_loader_gateway,
+        }
+    }
+}
+
+impl WgpuGraphicalAdapterPipelineFactory for SkyboxWgpuGraphicalAdapterPipelineFactory {
+    fn create(
+        &self,
+        device: &wgpu::Device,
+        _queue: &wgpu::Queue,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+        camera: &dyn Camera,
+        _lights: &[Light],
+    ) -> Box<dyn WgpuGraphicalAdapterPipeline> {
+        Box::new(SkyboxWgpuGraphicalAdapterPipeline::new(
+            device,
+            format,
+            sample_count,
+            camera,
+            self.cubemap_loader_gateway.clone(),
+        ))
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct SkyboxUniform {
+    inverse_projection: [[f32; 4]; 4],
+    inverse_view_rotation: [[f32; 4]; 4],
+}
+
+impl SkyboxUniform {
+    fn new(camera: &dyn Camera) -> Self {
+        let inverse_projection = camera.build_projection_matrix()
+            .invert()
+            .unwrap_or(cgmath::Matrix4::identity());
+        // Without the translation the skybox stays around the camera, and the inverse of a rotation is its transpose
+        let mut view_rotation = camera.build_view_matrix();
+        view_rotation.w = cgmath::Vector4::unit_w();
+        SkyboxUniform {
+            inverse_projection: inverse_projection.into(),
+            inverse_view_rotation: view_rotation.transpose().into(),
+        }
+    }
+}
+
+struct Environment {
+    id: String,
+    texture: Texture,
+    bind_group: wgpu::BindGroup,
+}
+
+pub struct SkyboxWgpuGraphicalAdapterPipeline {
+    cubemap_loader_gateway: Rc<dyn WgpuCubemapLoaderGateway>,
+    pipeline: wgpu::RenderPipeline,
+    environment_bind_group_layout: wgpu::BindGroupLayout,
+    environment: Option<Environment>,
+    skybox_buffer: wgpu::Buffer,
+    skybox_bind_group: wgpu::BindGroup,
+}
+
+impl SkyboxWgpuGraphicalAdapterPipeline {
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+        camera: &dyn Camera,
+        cubemap_loader_gateway: Rc<dyn WgpuCubemapLoaderGateway>,
+    ) -> SkyboxWgpuGraphicalAdapterPipeline {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Skybox Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("skybox.wgsl").into()),
+        });
+
+        let skybox_buffer = device.create_buffer_init(
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("Skybox Pipeline Camera Buffer"),
+                contents: bytemuck::cast_slice(&[SkyboxUniform::new(camera)]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            }
+        );
+
+        let skybox_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }
+            ],
+            label: Some("Skybox Pipeline Camera Bind Group Layout"),
+        });
+
+        let skybox_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &skybox_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: skybox_buffer.as_entire_binding(),
+                }
+            ],
+            label: Some("Skybox Pipeline Camera Bind Group"),
+        });
+
+        let environment_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::Cube,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+            label: Some("Skybox Pipeline Environment Bind Group Layout"),
+        });
+
+        let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Skybox Render Pipeline Layout"),
+            bind_group_layouts: &[
+                &skybox_bind_group_layout,
+                &environment_bind_group_layout,
+            ],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Skybox Render Pipeline"),
+            layout: Some(&render_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                ..Default::default()
+            },
+            // Drawn at the maximum depth, so it only shows where nothing else was or will be drawn
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: Texture::DEPTH_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache: None,
+        });
+
+        SkyboxWgpuGraphicalAdapterPipeline {
+            cubemap_loader_gateway,
+            pipeline,
+            environment_bind_group_layout,
+            environment: None,
+            skybox_buffer,
+            skybox_bind_group,
+        }
+    }
+}
+
+impl WgpuGraphicalAdapterPipeline for SkyboxWgpuGraphicalAdapterPipeline {
+    // There is a single environment, the current one must be unloaded first.
+    fn load_model_sync(
+        &mut self,
+        id: &str,
+        filename: &str,
+        _instances: Vec<Instance>,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> anyhow::Result<()> {
+        if let Some(environment) = &self.environment {
+            return Err(WgpuGraphicalAdapterError::ModelAlreadyLoaded(environment.id.clone()).into());
+        }
+        let texture = self.cubemap_loader_gateway.load_cubemap_sync(filename, device, queue)?;
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.environment_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&texture.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&texture.sampler),
+                },
+            ],
+            label: Some("Skybox Pipeline Environment Bind Group"),
+        });
+        self.environment = Some(Environment {
+            id: id.to_string(),
+            texture,
+            bind_group,
+        });
+        Ok(())
+    }
+
+    fn unload_model(&mut self, id: &str) -> anyhow::Result<()> {
+        match self.environment.take_if(|environment| environment.id == id) {
+            Some(environment) => {
+                environment.texture.destroy();
+                Ok(())
+            }
+            None => Err(WgpuGraphicalAdapterError::ModelNotFound(id.to_string()).into()),
+        }
+    }
+
+    fn has_model(&self, id: &str) -> bool {
+        self.environment.as_ref().is_some_and(|environment| environment.id == id)
+    }
+
+    fn update_camera(&mut self, camera: &dyn Camera, queue: &wgpu::Queue) {
+        queue.write_buffer(&self.skybox_buffer, 0, bytemuck::cast_slice(&[SkyboxUniform::new(camera)]));
+    }
+
+    fn update_lights(&mut self, _lights: &[Light], _queue: &wgpu::Queue) {}
+
+    fn render<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        if let Some(environment) = &self.environment {
+            log::debug!("Drawing skybox: {}", environment.id);
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_bind_group(0, &self.skybox_bind_group, &[]);
+            render_pass.set_bind_group(1, &environment.bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+    }
+
+    fn update_model_instances(
+        &mut self,
+        model_id: &str,
+        _instances: Vec<Instance>,
+        _device: &wgpu::Device,
+        _queue: &wgpu::Queue,
+    ) -> anyhow::Result<()> {
+        if !self.has_model(model_id) {
+            return Err(WgpuGraphicalAdapterError::ModelNotFound(model_id.to_string()).into());
+        }
+        Err(anyhow::anyhow!("The skybox has no instances: {}", model_id))
+    }
+
+    fn update_model_instances_range(
+        &mut self,
+        model_id: &str,
+        _first_instance: u32,
+        _instances: Vec<Instance>,
+        _queue: &wgpu::Queue,
+    ) -> anyhow::Result<()> {
+        if !self.has_model(model_id) {
+            return Err(WgpuGraphicalAdapterError::ModelNotFound(model_id.to_string()).into());
+        }
+        Err(anyhow::anyhow!("The skybox has no instances: {}", model_id))
+    }
+
+    fn list_models(&self) -> Vec<&str> {
+        self.environment.iter().map(|environment| environment.id.as_str()).collect()
+    }
+
+    // There is a single environment, so there is nothing to order.
+    fn set_model_order(&mut self, model_id: &str, _order: i32) -> anyhow::Result<()> {
+        if !self.has_model(model_id) {
+            return Err(WgpuGraphicalAdapterError::ModelNotFound(model_id.to_string()).into());
+        }
+        Ok(())
+    }
+}
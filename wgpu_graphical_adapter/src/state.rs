@@ -1,10 +1,14 @@
-use std::collections::HashMap;
 use std::path::Path;
 use anyhow::Context;
+use common::window_adapter::WindowAdapter;
 use pollster::FutureExt;
-use crate::camera::PerspectiveCamera;
+use crate::background::{Background, BackgroundRenderer};
+use crate::camera::Camera;
+use crate::config::{FallbackAdapter, RendererConfig};
+use crate::errors::WgpuGraphicalAdapterError;
 use crate::instance::Instance;
-use crate::pipeline::{WgpuGraphicalAdapterPipeline, WgpuGraphicalAdapterPipelineFactory};
+use crate::light::{Light, LightKind, MAX_LIGHTS};
+use crate::pipeline::{PipelineRegistration, WgpuGraphicalAdapterPipeline};
 use crate::texture::{RenderTargetTexture, Texture};
 
 pub trait WgpuGraphicalAdapterState {
@@ -14,23 +18,114 @@ pub trait WgpuGraphicalAdapterState {
                        filename: &str,
                        instances: Vec<Instance>,
     ) -> anyhow::Result<()>;
-    fn get_camera(&self) -> &PerspectiveCamera;
-    fn update_camera_eye(&mut self, eye: cgmath::Point3<f32>);
+    fn unload_model(&mut self, pipeline_id: &str, model_id: &str) -> anyhow::Result<()>;
+    fn has_model(&self, pipeline_id: &str, model_id: &str) -> anyhow::Result<bool>;
+    // Model ids of a pipeline in draw order
+    fn list_models(&self, pipeline_id: &str) -> anyhow::Result<Vec<&str>>;
+    fn get_camera(&self) -> &dyn Camera;
+    // Replaces the camera, its aspect ratio is set to the one of the render target.
+    // Camera changes are uploaded once, by the next render.
+    fn set_camera(&mut self, camera: Box<dyn Camera>);
+    fn update_camera(&mut self, update: &mut dyn FnMut(&mut dyn Camera));
+    // Moves the camera, keeping its target and up direction
+    fn update_camera_eye(&mut self, eye: cgmath::Point3<f32>) {
+        self.update_camera(&mut |camera| {
+            let (target, up) = (camera.target(), camera.up());
+            camera.look_at(eye, target, up);
+        });
+    }
+    fn get_lights(&self) -> &[Light];
+    fn set_lights(&mut self, lights: Vec<Light>) -> anyhow::Result<()>;
+    fn get_background(&self) -> &Background;
+    fn set_background(&mut self, background: Background);
+    // Shorthand for a solid background
+    fn set_clear_color(&mut self, color: wgpu::Color) {
+        self.set_background(Background::Solid(color));
+    }
     fn update_model_instances(
         &mut self,
         pipeline_id: &str,
         model_id: &str,
         instances: Vec<Instance>
     ) -> anyhow::Result<()>;
+    // Replaces the instances starting at first_instance, without changing the instance count
+    fn update_model_instances_range(
+        &mut self,
+        pipeline_id: &str,
+        model_id: &str,
+        first_instance: u32,
+        instances: Vec<Instance>
+    ) -> anyhow::Result<()>;
+    // Pipeline ids in draw order
+    fn get_pipeline_ids(&self) -> Vec<&str>;
+    fn get_pipeline_order(&self, pipeline_id: &str) -> anyhow::Result<i32>;
+    fn set_pipeline_order(&mut self, pipeline_id: &str, order: i32) -> anyhow::Result<()>;
+    fn set_model_order(&mut self, pipeline_id: &str, model_id: &str, order: i32) -> anyhow::Result<()>;
+    fn resize(&mut self, size: common::Dimentions) -> anyhow::Result<()>;
     fn render(&mut self) -> anyhow::Result<()>;
 }
 
+// The attachments every render pass draws into, besides the final color target.
+struct RenderTargets {
+    size: common::Dimentions,
+    color_format: wgpu::TextureFormat,
+    sample_count: u32,
+    depth_texture: Texture,
+    // Rendered into and resolved to the final color target when multisampling is enabled.
+    multisampled_framebuffer: Option<wgpu::TextureView>,
+}
+
+impl RenderTargets {
+    fn new(
+        device: &wgpu::Device,
+        size: common::Dimentions,
+        color_format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> RenderTargets {
+        let depth_texture = Texture::new_depth_texture(
+            device,
+            size,
+            sample_count,
+            "depth_texture",
+        );
+        let multisampled_framebuffer = if sample_count > 1 {
+            Some(Texture::new_multisampled_framebuffer(
+                device,
+                size,
+                color_format,
+                sample_count,
+                "multisampled_framebuffer",
+            ))
+        } else {
+            None
+        };
+        RenderTargets {
+            size,
+            color_format,
+            sample_count,
+            depth_texture,
+            multisampled_framebuffer,
+        }
+    }
+}
+
+struct RegisteredPipeline {
+    id: String,
+    order: i32,
+    pipeline: Box<dyn WgpuGraphicalAdapterPipeline>,
+}
+
 pub struct CoreState {
     device: wgpu::Device,
     queue: wgpu::Queue,
-    depth_texture: Texture,
-    pub camera: PerspectiveCamera,
-    pub render_pipelines: HashMap<String, Box<dyn WgpuGraphicalAdapterPipeline>>,
+    render_targets: RenderTargets,
+    background: BackgroundRenderer,
+    pub camera: Box<dyn Camera>,
+    // Set when the camera changed since it was last uploaded
+    camera_changed: bool,
+    pub lights: Vec<Light>,
+    // Sorted by order, see set_pipeline_order
+    render_pipelines: Vec<RegisteredPipeline>,
 }
 
 impl<'a> CoreState {
@@ -40,43 +135,186 @@ impl<'a> CoreState {
                            filename: &str,
                            instances: Vec<Instance>,
     ) -> anyhow::Result<()> {
-        if let Some(pipeline) = self.render_pipelines.get_mut(pipeline_id) {
-            pipeline.load_model_sync(model_id, filename, instances, &self.device, &self.queue)
-        } else {
-            Err(anyhow::anyhow!("Pipeline not found: {}", pipeline_id))
+        let pipeline = Self::get_pipeline_mut(&mut self.render_pipelines, pipeline_id)?;
+        pipeline.pipeline.load_model_sync(model_id, filename, instances, &self.device, &self.queue)
+    }
+
+    pub fn unload_model(&mut self, pipeline_id: &str, model_id: &str) -> anyhow::Result<()> {
+        Self::get_pipeline_mut(&mut self.render_pipelines, pipeline_id)?.pipeline.unload_model(model_id)
+    }
+
+    pub fn has_model(&self, pipeline_id: &str, model_id: &str) -> anyhow::Result<bool> {
+        Ok(self.get_pipeline(pipeline_id)?.pipeline.has_model(model_id))
+    }
+
+    pub fn list_models(&self, pipeline_id: &str) -> anyhow::Result<Vec<&str>> {
+        Ok(self.get_pipeline(pipeline_id)?.pipeline.list_models())
+    }
+
+    pub fn set_camera(&mut self, mut camera: Box<dyn Camera>) {
+        camera.set_aspect(Self::aspect(self.render_targets.size));
+        self.camera = camera;
+        self.camera_changed = true;
+    }
+
+    pub fn update_camera(&mut self, update: &mut dyn FnMut(&mut dyn Camera)) {
+        update(self.camera.as_mut());
+        self.camera_changed = true;
+    }
+
+    // Called before rendering, so any number of camera changes in a frame cost a single upload
+    fn upload_camera(&mut self) {
+        if !self.camera_changed {
+            return;
+        }
+        for pipeline in self.render_pipelines.iter_mut() {
+            pipeline.pipeline.update_camera(self.camera.as_ref(), &self.queue);
         }
+        self.camera_changed = false;
     }
 
-    pub fn update_camera(&mut self) {
-        for (_, pipeline) in self.render_pipelines.iter_mut() {
-            pipeline.update_camera(&self.camera, &self.queue);
+    fn aspect(size: common::Dimentions) -> f32 {
+        size.width as f32 / size.height as f32
+    }
+
+    pub fn set_lights(&mut self, lights: Vec<Light>) -> anyhow::Result<()> {
+        if lights.len() > MAX_LIGHTS {
+            return Err(anyhow::anyhow!("Too many lights: {}, the maximum is {}", lights.len(), MAX_LIGHTS));
+        }
+        self.lights = lights;
+        for pipeline in self.render_pipelines.iter_mut() {
+            pipeline.pipeline.update_lights(&self.lights, &self.queue);
         }
+        Ok(())
+    }
+
+    pub fn set_background(&mut self, background: Background) {
+        self.background.set_background(background, &self.queue);
     }
 
     pub fn update_model_instances(&mut self, pipeline_id: &str, model_id: &str, instances: Vec<Instance>) -> anyhow::Result<()> {
-        if let Some(pipeline) = self.render_pipelines.get_mut(pipeline_id) {
-            pipeline.update_model_instances(model_id, instances, &self.device)
-        } else {
-            Err(anyhow::anyhow!("Pipeline not found: {}", pipeline_id))
+        let pipeline = Self::get_pipeline_mut(&mut self.render_pipelines, pipeline_id)?;
+        pipeline.pipeline.update_model_instances(model_id, instances, &self.device, &self.queue)
+    }
+
+    pub fn update_model_instances_range(
+        &mut self,
+        pipeline_id: &str,
+        model_id: &str,
+        first_instance: u32,
+        instances: Vec<Instance>,
+    ) -> anyhow::Result<()> {
+        let pipeline = Self::get_pipeline_mut(&mut self.render_pipelines, pipeline_id)?;
+        pipeline.pipeline.update_model_instances_range(model_id, first_instance, instances, &self.queue)
+    }
+
+    pub fn get_pipeline_ids(&self) -> Vec<&str> {
+        self.render_pipelines.iter().map(|pipeline| pipeline.id.as_str()).collect()
+    }
+
+    pub fn get_pipeline_order(&self, pipeline_id: &str) -> anyhow::Result<i32> {
+        Ok(self.get_pipeline(pipeline_id)?.order)
+    }
+
+    pub fn set_pipeline_order(&mut self, pipeline_id: &str, order: i32) -> anyhow::Result<()> {
+        Self::get_pipeline_mut(&mut self.render_pipelines, pipeline_id)?.order = order;
+        // Stable, so pipelines with the same order stay in their registration order
+        self.render_pipelines.sort_by_key(|pipeline| pipeline.order);
+        Ok(())
+    }
+
+    pub fn set_model_order(&mut self, pipeline_id: &str, model_id: &str, order: i32) -> anyhow::Result<()> {
+        Self::get_pipeline_mut(&mut self.render_pipelines, pipeline_id)?.pipeline.set_model_order(model_id, order)
+    }
+
+    fn get_pipeline(&self, pipeline_id: &str) -> anyhow::Result<&RegisteredPipeline> {
+        self.render_pipelines.iter()
+            .find(|pipeline| pipeline.id == pipeline_id)
+            .ok_or_else(|| WgpuGraphicalAdapterError::PipelineNotFound(pipeline_id.to_string()).into())
+    }
+
+    // Only borrows the pipelines, so the device and queue can be passed to the pipeline
+    fn get_pipeline_mut<'b>(
+        render_pipelines: &'b mut [RegisteredPipeline],
+        pipeline_id: &str,
+    ) -> anyhow::Result<&'b mut RegisteredPipeline> {
+        render_pipelines.iter_mut()
+            .find(|pipeline| pipeline.id == pipeline_id)
+            .ok_or_else(|| WgpuGraphicalAdapterError::PipelineNotFound(pipeline_id.to_string()).into())
+    }
+
+    // Draws the background, then every pipeline in order
+    // upload_camera must be called first
+    fn render<'b>(&'b self, render_pass: &mut wgpu::RenderPass<'b>) {
+        self.background.render(render_pass);
+        for pipeline in self.render_pipelines.iter() {
+            pipeline.pipeline.render(render_pass);
         }
     }
 
+    pub fn resize(&mut self, size: common::Dimentions) -> anyhow::Result<()> {
+        Self::validate_size(&size)?;
+        self.render_targets = RenderTargets::new(
+            &self.device,
+            size,
+            self.render_targets.color_format,
+            self.render_targets.sample_count,
+        );
+        self.camera.set_aspect(Self::aspect(size));
+        self.camera_changed = true;
+        Ok(())
+    }
+
     fn new(
         device: wgpu::Device,
         queue: wgpu::Queue,
-        depth_texture: Texture,
-        camera: PerspectiveCamera,
-        render_pipelines: HashMap<String, Box<dyn WgpuGraphicalAdapterPipeline>>,
+        render_targets: RenderTargets,
+        camera: Box<dyn Camera>,
+        lights: Vec<Light>,
+        render_pipelines: Vec<RegisteredPipeline>,
     ) -> CoreState {
+        let background = BackgroundRenderer::new(
+            &device,
+            render_targets.color_format,
+            render_targets.sample_count,
+            Background::default(),
+        );
         CoreState {
             device,
             queue,
-            depth_texture,
+            render_targets,
+            background,
             camera,
+            camera_changed: false,
+            lights,
             render_pipelines,
         }
     }
 
+    fn create_pipelines(
+        registrations: Vec<PipelineRegistration>,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+        camera: &dyn Camera,
+        lights: &[Light],
+    ) -> anyhow::Result<Vec<RegisteredPipeline>> {
+        let mut render_pipelines: Vec<RegisteredPipeline> = Vec::with_capacity(registrations.len());
+        for registration in registrations {
+            if render_pipelines.iter().any(|pipeline| pipeline.id == registration.id) {
+                return Err(anyhow::anyhow!("Pipeline registered twice: {}", registration.id));
+            }
+            render_pipelines.push(RegisteredPipeline {
+                pipeline: registration.factory.create(device, queue, format, sample_count, camera, lights),
+                id: registration.id,
+                order: registration.order,
+            });
+        }
+        render_pipelines.sort_by_key(|pipeline| pipeline.order);
+        Ok(render_pipelines)
+    }
+
     fn validate_size(size: &common::Dimentions) -> anyhow::Result<()> {
         if (size.width == 0) || (size.height == 0) {
             return Err(anyhow::anyhow!("Invalid screen size: width: {}, height: {}", size.width, size.height));
@@ -84,35 +322,97 @@ impl<'a> CoreState {
         Ok(())
     }
 
-    fn initialize_instance() -> wgpu::Instance {
-        log::info!("Initializing wgpu...");
+    // The sample counts of the adapter are only usable when the device has
+    // TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES, without it only the guaranteed ones are.
+    fn validate_sample_count(
+        adapter: &wgpu::Adapter,
+        device: &wgpu::Device,
+        color_format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> anyhow::Result<()> {
+        if ![1, 2, 4, 8].contains(&sample_count) {
+            return Err(anyhow::anyhow!("Invalid sample count: {}, expected 1, 2, 4 or 8", sample_count));
+        }
+        for format in [color_format, Texture::DEPTH_FORMAT] {
+            let format_features = if device.features().contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES) {
+                adapter.get_texture_format_features(format)
+            } else {
+                format.guaranteed_format_features(device.features())
+            };
+            if !format_features.flags.sample_count_supported(sample_count) {
+                return Err(anyhow::anyhow!("Sample count {} is not supported for {:?} by this adapter", sample_count, format));
+            }
+        }
+        Ok(())
+    }
+
+    fn initialize_instance(config: &RendererConfig) -> wgpu::Instance {
+        log::info!("Initializing wgpu with backends {:?}...", config.backends);
         let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
-            backends: wgpu::Backends::PRIMARY,
+            backends: config.backends,
             ..Default::default()
         });
         log::info!("Finished initializing wgpu.");
         instance
     }
 
-    async fn request_adapter(instance: wgpu::Instance, surface: Option<&wgpu::Surface<'a>>) -> anyhow::Result<wgpu::Adapter> {
+    async fn request_adapter(
+        instance: wgpu::Instance,
+        surface: Option<&wgpu::Surface<'a>>,
+        config: &RendererConfig,
+    ) -> anyhow::Result<wgpu::Adapter> {
         log::info!("Requesting adapter...");
-        let adapter = instance.request_adapter(
+        let request = |force_fallback_adapter| instance.request_adapter(
             &wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::default(),
+                power_preference: config.power_preference,
                 compatible_surface: surface,
-                force_fallback_adapter: false, // we only want a hardware adapter
+                force_fallback_adapter,
             },
-        ).await.context("Unable to request WGPU adapter")?;
-        log::info!("Finished requesting adapter.");
+        );
+        let adapter = match config.fallback_adapter {
+            FallbackAdapter::Never => request(false).await
+                // Software Vulkan drivers are not reported as fallback adapters by wgpu
+                .filter(|adapter| adapter.get_info().device_type != wgpu::DeviceType::Cpu),
+            FallbackAdapter::Allow => match request(false).await {
+                Some(adapter) => Some(adapter),
+                None => {
+                    log::warn!("No hardware adapter found, requesting a fallback adapter...");
+                    request(true).await
+                }
+            },
+            FallbackAdapter::Force => request(true).await,
+        }.with_context(|| format!(
+            "Unable to request WGPU adapter for backends {:?} with fallback adapter {:?}",
+            config.backends, config.fallback_adapter,
+        ))?;
+        let info = adapter.get_info();
+        log::info!(
+            "Finished requesting adapter: {} ({:?}, {:?}), driver: {} {}",
+            info.name, info.backend, info.device_type, info.driver, info.driver_info,
+        );
         Ok(adapter)
     }
 
-    async fn request_device_and_queue(adapter: &wgpu::Adapter) -> anyhow::Result<(wgpu::Device, wgpu::Queue)> {
+    async fn request_device_and_queue(
+        adapter: &wgpu::Adapter,
+        config: &RendererConfig,
+        sample_count: u32,
+    ) -> anyhow::Result<(wgpu::Device, wgpu::Queue)> {
         log::info!("Requesting device and queue...");
+        let missing_features = config.required_features - adapter.features();
+        if !missing_features.is_empty() {
+            return Err(anyhow::anyhow!("Features not supported by this adapter: {:?}", missing_features));
+        }
+        let mut required_features = config.required_features;
+        // Only 1 and 4 samples are guaranteed, the others depend on the adapter
+        if ![1, 4].contains(&sample_count)
+            && adapter.features().contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES) {
+            required_features |= wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES;
+        }
         let (device, queue) = adapter.request_device(
             &wgpu::DeviceDescriptor {
-                required_features: wgpu::Features::empty(),
-                required_limits: wgpu::Limits::default(),
+                required_features,
+                required_limits: config.required_limits.clone(),
                 label: None,
                 memory_hints: wgpu::MemoryHints::Performance,
             },
@@ -122,9 +422,29 @@ impl<'a> CoreState {
         Ok((device, queue))
     }
 
+    // Draws into the multisampled framebuffer and resolves it to the target when multisampling is enabled.
+    fn get_render_pass_color_attachment<'b>(
+        &'b self,
+        target: &'b wgpu::TextureView,
+    ) -> wgpu::RenderPassColorAttachment<'b> {
+        let (view, resolve_target, store) = match &self.render_targets.multisampled_framebuffer {
+            // The samples are not needed anymore once resolved
+            Some(framebuffer) => (framebuffer, Some(target), wgpu::StoreOp::Discard),
+            None => (target, None, wgpu::StoreOp::Store),
+        };
+        wgpu::RenderPassColorAttachment {
+            view,
+            resolve_target,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Clear(self.background.clear_color()),
+                store,
+            },
+        }
+    }
+
     fn get_render_pass_stencil_attachment(&self) -> wgpu::RenderPassDepthStencilAttachment {
         wgpu::RenderPassDepthStencilAttachment {
-            view: &self.depth_texture.view,
+            view: &self.render_targets.depth_texture.view,
             depth_ops: Some(wgpu::Operations {
                 load: wgpu::LoadOp::Clear(1.0),
                 store: wgpu::StoreOp::Store,
@@ -137,6 +457,7 @@ impl<'a> CoreState {
 pub struct WgpuGraphicalAdapterStateWithWindow<'a> {
     core_state: CoreState,
     surface: wgpu::Surface<'a>,
+    configuration: wgpu::SurfaceConfiguration,
 }
 
 impl<'a> WgpuGraphicalAdapterStateWithWindow<'a> {
@@ -144,38 +465,38 @@ impl<'a> WgpuGraphicalAdapterStateWithWindow<'a> {
     pub async fn new(
         window: wgpu::SurfaceTarget<'a>,
         size: common::Dimentions,
-        factories: HashMap<String, Box<dyn WgpuGraphicalAdapterPipelineFactory>>,
+        sample_count: u32,
+        config: &RendererConfig,
+        mut camera: Box<dyn Camera>,
+        pipelines: Vec<PipelineRegistration>,
     ) -> anyhow::Result<WgpuGraphicalAdapterStateWithWindow<'a>> {
         CoreState::validate_size(&size)?;
 
-        let instance = CoreState::initialize_instance();
+        let instance = CoreState::initialize_instance(config);
         let surface = Self::create_surface(window, &instance)?;
-        let adapter = CoreState::request_adapter(instance, Some(&surface)).await?;
-        let (device, queue) = CoreState::request_device_and_queue(&adapter).await?;
-        let configuration = Self::configure_surface(&size, &surface, &adapter, &device);
+        let adapter = CoreState::request_adapter(instance, Some(&surface), config).await?;
+        let (device, queue) = CoreState::request_device_and_queue(&adapter, config, sample_count).await?;
+        let configuration = Self::configure_surface(&size, &surface, &adapter, &device, config.present_mode);
+        CoreState::validate_sample_count(&adapter, &device, configuration.format, sample_count)?;
 
-        let depth_texture = Texture::new_depth_texture(
+        let render_targets = RenderTargets::new(&device, size, configuration.format, sample_count);
+        camera.set_aspect(CoreState::aspect(size));
+        let lights = make_default_lights();
+
+        let render_pipelines = CoreState::create_pipelines(
+            pipelines,
             &device,
-            common::Dimentions {
-                width: size.width,
-                height: size.height,
-            },
-            "depth_texture",
-        );
-        let camera = make_camera(size);
-
-        let mut render_pipelines: HashMap<String, Box<dyn WgpuGraphicalAdapterPipeline>> = HashMap::new();
-        for (name, factory) in factories {
-            render_pipelines.insert(name, factory.create(
-                &device,
-                configuration.format,
-                &camera,
-            ));
-        }
+            &queue,
+            configuration.format,
+            sample_count,
+            camera.as_ref(),
+            &lights,
+        )?;
 
         Ok(WgpuGraphicalAdapterStateWithWindow {
-            core_state: CoreState::new(device, queue, depth_texture, camera, render_pipelines),
+            core_state: CoreState::new(device, queue, render_targets, camera, lights, render_pipelines),
             surface,
+            configuration,
         })
     }
 
@@ -191,6 +512,7 @@ impl<'a> WgpuGraphicalAdapterStateWithWindow<'a> {
         surface: &wgpu::Surface,
         adapter: &wgpu::Adapter,
         device: &wgpu::Device,
+        present_mode: wgpu::PresentMode,
     ) -> wgpu::SurfaceConfiguration {
         log::info!("Configuring surface...");
         let surface_caps = surface.get_capabilities(&adapter);
@@ -201,12 +523,20 @@ impl<'a> WgpuGraphicalAdapterStateWithWindow<'a> {
             .find(|f| f.is_srgb())
             .copied()
             .unwrap_or(surface_caps.formats[0]);
+        // The automatic modes pick a supported one themselves
+        let automatic = matches!(present_mode, wgpu::PresentMode::AutoVsync | wgpu::PresentMode::AutoNoVsync);
+        let present_mode = if automatic || surface_caps.present_modes.contains(&present_mode) {
+            present_mode
+        } else {
+            log::warn!("Present mode {:?} is not supported by the surface, using Fifo", present_mode);
+            wgpu::PresentMode::Fifo
+        };
         let config = wgpu::SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
             format: surface_format,
             width: size.width,
             height: size.height,
-            present_mode: wgpu::PresentMode::Fifo, // vsync, will always be supported.
+            present_mode,
             alpha_mode: surface_caps.alpha_modes[0],
             view_formats: vec![],
             desired_maximum_frame_latency: 2,
@@ -215,6 +545,50 @@ impl<'a> WgpuGraphicalAdapterStateWithWindow<'a> {
         log::info!("Finished configuring surface...");
         config
     }
+
+    // Returns None when the frame should be skipped.
+    fn acquire_surface_texture(&mut self) -> anyhow::Result<Option<wgpu::SurfaceTexture>> {
+        match self.surface.get_current_texture() {
+            Ok(output) => Ok(Some(output)),
+            Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                log::warn!("Surface lost or outdated, reconfiguring...");
+                self.surface.configure(&self.core_state.device, &self.configuration);
+                match self.surface.get_current_texture() {
+                    Ok(output) => Ok(Some(output)),
+                    Err(wgpu::SurfaceError::OutOfMemory) => Err(WgpuGraphicalAdapterError::SurfaceOutOfMemory.into()),
+                    Err(error) => {
+                        log::warn!("Skipping frame, surface still unavailable after reconfiguring: {}", error);
+                        Ok(None)
+                    }
+                }
+            }
+            Err(wgpu::SurfaceError::Timeout) => {
+                log::warn!("Skipping frame, timed out while acquiring the surface texture.");
+                Ok(None)
+            }
+            Err(wgpu::SurfaceError::OutOfMemory) => Err(WgpuGraphicalAdapterError::SurfaceOutOfMemory.into()),
+        }
+    }
+}
+
+impl WgpuGraphicalAdapterStateWithWindow<'static> {
+    // Renders to the window of any backend, the surface keeps the window alive.
+    pub async fn from_window_adapter(
+        window_adapter: &dyn WindowAdapter,
+        sample_count: u32,
+        config: &RendererConfig,
+        camera: Box<dyn Camera>,
+        pipelines: Vec<PipelineRegistration>,
+    ) -> anyhow::Result<WgpuGraphicalAdapterStateWithWindow<'static>> {
+        Self::new(
+            window_adapter.get_window_handle().into(),
+            window_adapter.get_framebuffer_size(),
+            sample_count,
+            config,
+            camera,
+            pipelines,
+        ).await
+    }
 }
 
 impl <'a> WgpuGraphicalAdapterState for WgpuGraphicalAdapterStateWithWindow<'a> {
@@ -222,22 +596,95 @@ impl <'a> WgpuGraphicalAdapterState for WgpuGraphicalAdapterStateWithWindow<'a>
         self.core_state.load_model_sync(pipeline_id, model_id, filename, instances)
     }
 
-    fn get_camera(&self) -> &PerspectiveCamera {
-        &self.core_state.camera
+    fn unload_model(&mut self, pipeline_id: &str, model_id: &str) -> anyhow::Result<()> {
+        self.core_state.unload_model(pipeline_id, model_id)
     }
 
-    fn update_camera_eye(&mut self, eye: cgmath::Point3<f32>) {
-        self.core_state.camera.eye = eye;
-        self.core_state.update_camera();
+    fn has_model(&self, pipeline_id: &str, model_id: &str) -> anyhow::Result<bool> {
+        self.core_state.has_model(pipeline_id, model_id)
+    }
+
+    fn list_models(&self, pipeline_id: &str) -> anyhow::Result<Vec<&str>> {
+        self.core_state.list_models(pipeline_id)
+    }
+
+    fn get_camera(&self) -> &dyn Camera {
+        self.core_state.camera.as_ref()
+    }
+
+    fn set_camera(&mut self, camera: Box<dyn Camera>) {
+        self.core_state.set_camera(camera);
+    }
+
+    fn update_camera(&mut self, update: &mut dyn FnMut(&mut dyn Camera)) {
+        self.core_state.update_camera(update);
+    }
+
+    fn get_lights(&self) -> &[Light] {
+        &self.core_state.lights
+    }
+
+    fn set_lights(&mut self, lights: Vec<Light>) -> anyhow::Result<()> {
+        self.core_state.set_lights(lights)
+    }
+
+    fn get_background(&self) -> &Background {
+        self.core_state.background.get_background()
+    }
+
+    fn set_background(&mut self, background: Background) {
+        self.core_state.set_background(background);
     }
 
     fn update_model_instances(&mut self, pipeline_id: &str, model_id: &str, instances: Vec<Instance>) -> anyhow::Result<()> {
         self.core_state.update_model_instances(pipeline_id, model_id, instances)
     }
 
+    fn update_model_instances_range(
+        &mut self,
+        pipeline_id: &str,
+        model_id: &str,
+        first_instance: u32,
+        instances: Vec<Instance>
+    ) -> anyhow::Result<()> {
+        self.core_state.update_model_instances_range(pipeline_id, model_id, first_instance, instances)
+    }
+
+    fn get_pipeline_ids(&self) -> Vec<&str> {
+        self.core_state.get_pipeline_ids()
+    }
+
+    fn get_pipeline_order(&self, pipeline_id: &str) -> anyhow::Result<i32> {
+        self.core_state.get_pipeline_order(pipeline_id)
+    }
+
+    fn set_pipeline_order(&mut self, pipeline_id: &str, order: i32) -> anyhow::Result<()> {
+        self.core_state.set_pipeline_order(pipeline_id, order)
+    }
+
+    fn set_model_order(&mut self, pipeline_id: &str, model_id: &str, order: i32) -> anyhow::Result<()> {
+        self.core_state.set_model_order(pipeline_id, model_id, order)
+    }
+
+    fn resize(&mut self, size: common::Dimentions) -> anyhow::Result<()> {
+        // A minimized window reports a zero sized framebuffer, there is nothing to render to.
+        if (size.width == 0) || (size.height == 0) {
+            log::debug!("Ignoring resize to an empty surface: width: {}, height: {}", size.width, size.height);
+            return Ok(());
+        }
+        self.configuration.width = size.width;
+        self.configuration.height = size.height;
+        self.surface.configure(&self.core_state.device, &self.configuration);
+        self.core_state.resize(size)
+    }
+
     fn render(&mut self) -> anyhow::Result<()> {
-        let output = self.surface.get_current_texture()?;
+        let output = match self.acquire_surface_texture()? {
+            Some(output) => output,
+            None => return Ok(()),
+        };
         let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
+        self.core_state.upload_camera();
         let mut encoder = self.core_state.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("Render Encoder"),
         });
@@ -245,27 +692,13 @@ impl <'a> WgpuGraphicalAdapterState for WgpuGraphicalAdapterStateWithWindow<'a>
         {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.1,
-                            g: 0.2,
-                            b: 0.3,
-                            a: 1.0,
-                        }),
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
+                color_attachments: &[Some(self.core_state.get_render_pass_color_attachment(&view))],
                 depth_stencil_attachment: Some(self.core_state.get_render_pass_stencil_attachment()),
                 occlusion_query_set: None,
                 timestamp_writes: None,
             });
 
-            for (_, render_pipeline) in self.core_state.render_pipelines.iter() {
-                render_pipeline.render(&mut render_pass);
-            }
+            self.core_state.render(&mut render_pass);
         }
 
         // submit will accept anything that implements IntoIter
@@ -285,41 +718,42 @@ pub struct WgpuGraphicalAdapterStateRenderToDisk {
 impl WgpuGraphicalAdapterStateRenderToDisk {
     pub async fn new(
         size: common::Dimentions,
-        factories: HashMap<String, Box<dyn WgpuGraphicalAdapterPipelineFactory>>,
+        sample_count: u32,
+        config: &RendererConfig,
+        mut camera: Box<dyn Camera>,
+        pipelines: Vec<PipelineRegistration>,
         output_path: Box<Path>,
     ) -> anyhow::Result<WgpuGraphicalAdapterStateRenderToDisk> {
         CoreState::validate_size(&size)?;
 
-        let instance = CoreState::initialize_instance();
-        let adapter = CoreState::request_adapter(instance, None).await?;
-        let (device, queue) = CoreState::request_device_and_queue(&adapter).await?;
+        let instance = CoreState::initialize_instance(config);
+        let adapter = CoreState::request_adapter(instance, None, config).await?;
+        let (device, queue) = CoreState::request_device_and_queue(&adapter, config, sample_count).await?;
 
         let render_target_texture = RenderTargetTexture::new(
             &device,
             &size,
             "render_target_texture"
         );
-        let depth_texture = Texture::new_depth_texture(
+        let color_format = render_target_texture.texture.format();
+        CoreState::validate_sample_count(&adapter, &device, color_format, sample_count)?;
+
+        let render_targets = RenderTargets::new(&device, size, color_format, sample_count);
+        camera.set_aspect(CoreState::aspect(size));
+        let lights = make_default_lights();
+
+        let render_pipelines = CoreState::create_pipelines(
+            pipelines,
             &device,
-            common::Dimentions {
-                width: size.width,
-                height: size.height,
-            },
-            "depth_texture",
-        );
-        let camera = make_camera(size);
-
-        let mut render_pipelines: HashMap<String, Box<dyn WgpuGraphicalAdapterPipeline>> = HashMap::new();
-        for (name, factory) in factories {
-            render_pipelines.insert(name, factory.create(
-                &device,
-                render_target_texture.texture.format(),
-                &camera,
-            ));
-        }
+            &queue,
+            color_format,
+            sample_count,
+            camera.as_ref(),
+            &lights,
+        )?;
 
         Ok(WgpuGraphicalAdapterStateRenderToDisk {
-            core_state: CoreState::new(device, queue, depth_texture, camera, render_pipelines),
+            core_state: CoreState::new(device, queue, render_targets, camera, lights, render_pipelines),
             render_target_texture,
             output_path,
         })
@@ -331,20 +765,88 @@ impl WgpuGraphicalAdapterState for WgpuGraphicalAdapterStateRenderToDisk {
         self.core_state.load_model_sync(pipeline_id, model_id, filename, instances)
     }
 
-    fn get_camera(&self) -> &PerspectiveCamera {
-        &self.core_state.camera
+    fn unload_model(&mut self, pipeline_id: &str, model_id: &str) -> anyhow::Result<()> {
+        self.core_state.unload_model(pipeline_id, model_id)
     }
 
-    fn update_camera_eye(&mut self, eye: cgmath::Point3<f32>) {
-        self.core_state.camera.eye = eye;
-        self.core_state.update_camera();
+    fn has_model(&self, pipeline_id: &str, model_id: &str) -> anyhow::Result<bool> {
+        self.core_state.has_model(pipeline_id, model_id)
+    }
+
+    fn list_models(&self, pipeline_id: &str) -> anyhow::Result<Vec<&str>> {
+        self.core_state.list_models(pipeline_id)
+    }
+
+    fn get_camera(&self) -> &dyn Camera {
+        self.core_state.camera.as_ref()
+    }
+
+    fn set_camera(&mut self, camera: Box<dyn Camera>) {
+        self.core_state.set_camera(camera);
+    }
+
+    fn update_camera(&mut self, update: &mut dyn FnMut(&mut dyn Camera)) {
+        self.core_state.update_camera(update);
+    }
+
+    fn get_lights(&self) -> &[Light] {
+        &self.core_state.lights
+    }
+
+    fn set_lights(&mut self, lights: Vec<Light>) -> anyhow::Result<()> {
+        self.core_state.set_lights(lights)
+    }
+
+    fn get_background(&self) -> &Background {
+        self.core_state.background.get_background()
+    }
+
+    fn set_background(&mut self, background: Background) {
+        self.core_state.set_background(background);
     }
 
     fn update_model_instances(&mut self, pipeline_id: &str, model_id: &str, instances: Vec<Instance>) -> anyhow::Result<()> {
         self.core_state.update_model_instances(pipeline_id, model_id, instances)
     }
 
+    fn update_model_instances_range(
+        &mut self,
+        pipeline_id: &str,
+        model_id: &str,
+        first_instance: u32,
+        instances: Vec<Instance>
+    ) -> anyhow::Result<()> {
+        self.core_state.update_model_instances_range(pipeline_id, model_id, first_instance, instances)
+    }
+
+    fn get_pipeline_ids(&self) -> Vec<&str> {
+        self.core_state.get_pipeline_ids()
+    }
+
+    fn get_pipeline_order(&self, pipeline_id: &str) -> anyhow::Result<i32> {
+        self.core_state.get_pipeline_order(pipeline_id)
+    }
+
+    fn set_pipeline_order(&mut self, pipeline_id: &str, order: i32) -> anyhow::Result<()> {
+        self.core_state.set_pipeline_order(pipeline_id, order)
+    }
+
+    fn set_model_order(&mut self, pipeline_id: &str, model_id: &str, order: i32) -> anyhow::Result<()> {
+        self.core_state.set_model_order(pipeline_id, model_id, order)
+    }
+
+    fn resize(&mut self, size: common::Dimentions) -> anyhow::Result<()> {
+        CoreState::validate_size(&size)?;
+        self.render_target_texture = RenderTargetTexture::new(
+            &self.core_state.device,
+            &size,
+            "render_target_texture"
+        );
+        self.core_state.resize(size)
+    }
+
     fn render(&mut self) -> anyhow::Result<()> {
+        self.core_state.upload_camera();
         let mut encoder = self.core_state.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("Render Encoder"),
         });
@@ -352,27 +854,13 @@ impl WgpuGraphicalAdapterState for WgpuGraphicalAdapterStateRenderToDisk {
         {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &self.render_target_texture.view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.1,
-                            g: 0.2,
-                            b: 0.3,
-                            a: 1.0,
-                        }),
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
+                color_attachments: &[Some(self.core_state.get_render_pass_color_attachment(&self.render_target_texture.view))],
                 depth_stencil_attachment: Some(self.core_state.get_render_pass_stencil_attachment()),
                 occlusion_query_set: None,
                 timestamp_writes: None,
             });
 
-            for (_, render_pipeline) in self.core_state.render_pipelines.iter() {
-                render_pipeline.render(&mut render_pass);
-            }
+            self.core_state.render(&mut render_pass);
         }
 
         encoder.copy_texture_to_buffer(
@@ -386,8 +874,7 @@ impl WgpuGraphicalAdapterState for WgpuGraphicalAdapterStateRenderToDisk {
                 buffer: &self.render_target_texture.output_buffer,
                 layout: wgpu::ImageDataLayout {
                     offset: 0,
-                    bytes_per_row: Some(size_of::<u32>() as u32
-                        * self.render_target_texture.dimensions.width),
+                    bytes_per_row: Some(self.render_target_texture.padded_bytes_per_row),
                     rows_per_image: Some(self.render_target_texture.dimensions.height),
                 },
             },
@@ -411,18 +898,13 @@ impl WgpuGraphicalAdapterState for WgpuGraphicalAdapterStateRenderToDisk {
     }
 }
 
-fn make_camera(size: common::Dimentions) -> PerspectiveCamera {
-    PerspectiveCamera {
-        // position the camera 1 unit up and 2 units back
-        // +z is out of the screen
-        eye: (0.0, 6.0, 20.0).into(),
-        // have it look at the origin
-        target: (0.0, 0.0, 0.0).into(),
-        // which way is "up"
-        up: cgmath::Vector3::unit_y(),
-        aspect: size.width as f32 / size.height as f32,
-        fovy: 45.0,
-        znear: 0.1,
-        zfar: 100.0,
-    }
-}
\ No newline at end of file
+fn make_default_lights() -> Vec<Light> {
+    vec![Light {
+        // Coming from the top right, behind the camera
+        kind: LightKind::Directional {
+            direction: cgmath::Vector3::new(-0.5, -1.0, -0.6),
+        },
+        color: [1.0, 1.0, 1.0],
+        intensity: 1.0,
+    }]
+}
@@ -2,6 +2,22 @@ use std::path::Path;
 use std::rc::Rc;
 use image::GenericImageView;
 use common::Dimentions;
+use crate::equirectangular::equirectangular_to_cubemap;
+use crate::mipmap::{mip_level_count, MipmapGenerator};
+
+#[derive(Clone, Copy, Debug)]
+pub struct TextureLoadOptions {
+    // Generates the full mip chain on the GPU, turn it off for textures always drawn at their size (UI).
+    pub generate_mipmaps: bool,
+}
+
+impl Default for TextureLoadOptions {
+    fn default() -> Self {
+        TextureLoadOptions {
+            generate_mipmaps: true,
+        }
+    }
+}
 
 pub struct Texture {
     pub texture: wgpu::Texture,
@@ -14,37 +30,135 @@ impl Texture {
 
     pub fn new_diffuse_texture_from_bytes(
         raw_data: Vec<u8>,
+        options: TextureLoadOptions,
+        mipmap_generator: &mut MipmapGenerator,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
     ) -> anyhow::Result<Texture> {
         let diffuse_image = image::load_from_memory(&raw_data)?;
+        // Most images are stored using sRGB, so we need to reflect that here.
+        Ok(Self::new_from_rgba8(
+            &diffuse_image.to_rgba8().into_raw(),
+            Dimentions {
+                width: diffuse_image.dimensions().0,
+                height: diffuse_image.dimensions().1,
+            },
+            wgpu::TextureFormat::Rgba8UnormSrgb,
+            options,
+            mipmap_generator,
+            device,
+            queue,
+        ))
+    }
+
+    pub fn new_normal_texture_from_bytes(
+        raw_data: Vec<u8>,
+        options: TextureLoadOptions,
+        mipmap_generator: &mut MipmapGenerator,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> anyhow::Result<Texture> {
+        let normal_image = image::load_from_memory(&raw_data)?;
+        // Normal maps hold directions, not colors, so they must not be converted from sRGB.
+        Ok(Self::new_from_rgba8(
+            &normal_image.to_rgba8().into_raw(),
+            Dimentions {
+                width: normal_image.dimensions().0,
+                height: normal_image.dimensions().1,
+            },
+            wgpu::TextureFormat::Rgba8Unorm,
+            options,
+            mipmap_generator,
+            device,
+            queue,
+        ))
+    }
+
+    // Frees the GPU memory right away, the texture must not be used anymore.
+    pub fn destroy(&self) {
+        self.texture.destroy();
+    }
+
+    // A 1x1 normal map pointing straight out of the surface, used by materials without one.
+    pub fn new_flat_normal_texture(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Texture {
+        Self::new_from_rgba8_with_mip_levels(
+            &[128, 128, 255, 255],
+            Dimentions {
+                width: 1,
+                height: 1,
+            },
+            wgpu::TextureFormat::Rgba8Unorm,
+            1,
+            device,
+            queue,
+        )
+    }
+
+    pub fn new_from_rgba8(
+        pixels: &[u8],
+        dimensions: Dimentions,
+        format: wgpu::TextureFormat,
+        options: TextureLoadOptions,
+        mipmap_generator: &mut MipmapGenerator,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Texture {
+        let mip_level_count = if options.generate_mipmaps {
+            mip_level_count(dimensions.width, dimensions.height)
+        } else {
+            1
+        };
+        let texture = Self::new_from_rgba8_with_mip_levels(pixels, dimensions, format, mip_level_count, device, queue);
+        mipmap_generator.generate_mipmaps(&texture.texture, device, queue);
+        texture
+    }
+
+    // Only the first mip level is filled.
+    fn new_from_rgba8_with_mip_levels(
+        pixels: &[u8],
+        dimensions: Dimentions,
+        format: wgpu::TextureFormat,
+        mip_level_count: u32,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Texture {
+        // The mip levels are rendered from each other
+        let usage = if mip_level_count > 1 {
+            wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::RENDER_ATTACHMENT
+        } else {
+            wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST
+        };
+
         let texture_size = wgpu::Extent3d {
-            width: diffuse_image.dimensions().0,
-            height: diffuse_image.dimensions().1,
+            width: dimensions.width,
+            height: dimensions.height,
             depth_or_array_layers: 1,
         };
 
-        let diffuse_texture = device.create_texture(
+        let texture = device.create_texture(
             &wgpu::TextureDescriptor {
                 // All textures are stored as 3D, we represent our 2D texture
                 // by setting depth to 1.
                 size: texture_size,
-                mip_level_count: 1,
+                mip_level_count,
                 sample_count: 1,
                 dimension: wgpu::TextureDimension::D2,
-                // Most images are stored using sRGB, so we need to reflect that here.
-                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                format,
                 // TEXTURE_BINDING tells wgpu that we want to use this texture in shaders
                 // COPY_DST means that we want to copy data to this texture
-                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                usage,
                 label: None,
                 // This is the same as with the SurfaceConfig. It
                 // specifies what texture formats can be used to
                 // create TextureViews for this texture. The base
-                // texture format (Rgba8UnormSrgb in this case) is
-                // always supported. Note that using a different
-                // texture format is not supported on the WebGL2
-                // backend.
+                // texture format is always supported. Note that
+                // using a different texture format is not supported
+                // on the WebGL2 backend.
                 view_formats: &[],
             }
         );
@@ -52,48 +166,204 @@ impl Texture {
         queue.write_texture(
             // Tells wgpu where to copy the pixel data
             wgpu::ImageCopyTexture {
-                texture: &diffuse_texture,
+                texture: &texture,
                 mip_level: 0,
                 origin: wgpu::Origin3d::ZERO,
                 aspect: wgpu::TextureAspect::All,
             },
             // The actual pixel data
-            &diffuse_image.to_rgba8().into_raw(),
+            pixels,
             // The layout of the texture
             wgpu::ImageDataLayout {
                 offset: 0,
-                bytes_per_row: Some(4 * diffuse_image.dimensions().0),
-                rows_per_image: Some(diffuse_image.dimensions().1),
+                bytes_per_row: Some(4 * dimensions.width),
+                rows_per_image: Some(dimensions.height),
             },
             texture_size,
         );
 
         // We don't need to configure the texture view much, so let's
         // let wgpu define it.
-        let diffuse_view = diffuse_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
 
-        // FIXME with proper tests and read about mipmaps
-        let diffuse_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+        // Trilinear filtering, plus anisotropic filtering for surfaces seen at grazing angles.
+        // Anisotropy requires every filter to be linear and is only useful with mipmaps.
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
             address_mode_u: wgpu::AddressMode::ClampToEdge,
             address_mode_v: wgpu::AddressMode::ClampToEdge,
             address_mode_w: wgpu::AddressMode::ClampToEdge,
             mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Nearest,
-            mipmap_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            anisotropy_clamp: if mip_level_count > 1 { 16 } else { 1 },
             ..Default::default()
             // Other parameters are at https://docs.rs/wgpu/latest/wgpu/struct.SamplerDescriptor.html
         });
 
-        Ok(Texture {
-            texture: diffuse_texture,
-            view: diffuse_view,
-            sampler: diffuse_sampler,
+        Texture {
+            texture,
+            view,
+            sampler,
+        }
+    }
+
+    // Faces are in the +X, -X, +Y, -Y, +Z, -Z order, they must be square and of the same size.
+    pub fn new_cubemap_from_faces(
+        faces: [Vec<u8>; 6],
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> anyhow::Result<Texture> {
+        let images = faces.iter()
+            .map(|raw_data| image::load_from_memory(raw_data).map(|image| image.to_rgba8()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let (width, height) = images[0].dimensions();
+        if width != height || images.iter().any(|image| image.dimensions() != (width, height)) {
+            return Err(anyhow::anyhow!(
+                "Cubemap faces must be square and of the same size, got {:?}",
+                images.iter().map(|image| image.dimensions()).collect::<Vec<_>>(),
+            ));
+        }
+
+        // Most images are stored using sRGB, like diffuse textures.
+        let texture = Self::create_cubemap_texture(
+            width,
+            wgpu::TextureFormat::Rgba8UnormSrgb,
+            wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            device,
+        );
+        for (layer, image) in images.iter().enumerate() {
+            queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    texture: &texture,
+                    mip_level: 0,
+                    // Each face is a layer of the texture
+                    origin: wgpu::Origin3d {
+                        x: 0,
+                        y: 0,
+                        z: layer as u32,
+                    },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                image.as_raw(),
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(4 * width),
+                    rows_per_image: Some(height),
+                },
+                wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+
+        Ok(Self::from_cubemap_texture(texture, device))
+    }
+
+    // Converts an equirectangular image, usually a Radiance HDR file, to a cubemap on the GPU.
+    // The faces are a quarter of the image width, which keeps about the same resolution around the horizon.
+    pub fn new_cubemap_from_equirectangular(
+        raw_data: Vec<u8>,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> anyhow::Result<Texture> {
+        let image = image::load_from_memory(&raw_data)?.to_rgba32f();
+        let (width, height) = image.dimensions();
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        let source = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Equirectangular Source Texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba32Float,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &source,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            bytemuck::cast_slice(image.as_raw()),
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * size_of::<f32>() as u32 * width),
+                rows_per_image: Some(height),
+            },
+            size,
+        );
+
+        // Keeps the high dynamic range, and unlike Rgba32Float it can be filtered and written by compute shaders everywhere.
+        let texture = Self::create_cubemap_texture(
+            (width / 4).max(1),
+            wgpu::TextureFormat::Rgba16Float,
+            wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::STORAGE_BINDING,
+            device,
+        );
+        equirectangular_to_cubemap(&source, &texture, device, queue);
+
+        Ok(Self::from_cubemap_texture(texture, device))
+    }
+
+    fn create_cubemap_texture(
+        face_size: u32,
+        format: wgpu::TextureFormat,
+        usage: wgpu::TextureUsages,
+        device: &wgpu::Device,
+    ) -> wgpu::Texture {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Cubemap Texture"),
+            size: wgpu::Extent3d {
+                width: face_size,
+                height: face_size,
+                // A cubemap is a 2D texture with one layer per face
+                depth_or_array_layers: 6,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage,
+            view_formats: &[],
         })
     }
 
+    fn from_cubemap_texture(texture: wgpu::Texture, device: &wgpu::Device) -> Texture {
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("Cubemap Texture View"),
+            dimension: Some(wgpu::TextureViewDimension::Cube),
+            ..Default::default()
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Cubemap Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        Texture {
+            texture,
+            view,
+            sampler,
+        }
+    }
+
     pub fn new_depth_texture(
         device: &wgpu::Device,
         size: Dimentions,
+        sample_count: u32,
         label: &str,
     ) -> Self {
         let size = wgpu::Extent3d {
@@ -101,14 +371,21 @@ impl Texture {
             height: size.height,
             depth_or_array_layers: 1,
         };
+        // Multisampled depth textures can't be sampled with the sampler below, and binding them
+        // breaks the resolve of the color attachment on the GL backend, which then stays empty.
+        let usage = if sample_count > 1 {
+            wgpu::TextureUsages::RENDER_ATTACHMENT
+        } else {
+            wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING
+        };
         let desc = wgpu::TextureDescriptor {
             label: Some(label),
             size,
             mip_level_count: 1,
-            sample_count: 1,
+            sample_count,
             dimension: wgpu::TextureDimension::D2,
             format: Self::DEPTH_FORMAT,
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            usage,
             view_formats: &[],
         };
         let texture = device.create_texture(&desc);
@@ -132,6 +409,31 @@ impl Texture {
 
         Self { texture, view, sampler }
     }
+
+    // Only ever used as a render attachment, so there is no need for a sampler.
+    pub fn new_multisampled_framebuffer(
+        device: &wgpu::Device,
+        size: Dimentions,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+        label: &str,
+    ) -> wgpu::TextureView {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d {
+                width: size.width,
+                height: size.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        texture.create_view(&wgpu::TextureViewDescriptor::default())
+    }
 }
 
 pub struct RenderTargetTexture {
@@ -139,6 +441,9 @@ pub struct RenderTargetTexture {
     pub view: Rc<wgpu::TextureView>,
     pub output_buffer: wgpu::Buffer,
     pub dimensions: Dimentions,
+    // Rows copied to the output buffer must be aligned to COPY_BYTES_PER_ROW_ALIGNMENT (256 bytes),
+    // so they are padded and the padding is stripped when writing the file.
+    pub padded_bytes_per_row: u32,
 }
 
 impl RenderTargetTexture {
@@ -166,9 +471,9 @@ impl RenderTargetTexture {
         let texture = device.create_texture(&texture_desc);
         let view = texture.create_view(&Default::default());
 
-        let u32_size = size_of::<u32>() as u32;
+        let padded_bytes_per_row = Self::padded_bytes_per_row(size.width);
 
-        let output_buffer_size = (u32_size * size.width * size.height) as wgpu::BufferAddress;
+        let output_buffer_size = (padded_bytes_per_row * size.height) as wgpu::BufferAddress;
         let output_buffer_desc = wgpu::BufferDescriptor {
             size: output_buffer_size,
             usage: wgpu::BufferUsages::COPY_DST
@@ -187,9 +492,20 @@ impl RenderTargetTexture {
                 width: size.width,
                 height: size.height,
             },
+            padded_bytes_per_row,
         }
     }
 
+    fn unpadded_bytes_per_row(width: u32) -> u32 {
+        size_of::<u32>() as u32 * width
+    }
+
+    fn padded_bytes_per_row(width: u32) -> u32 {
+        let unpadded_bytes_per_row = Self::unpadded_bytes_per_row(width);
+        let alignment = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        unpadded_bytes_per_row.div_ceil(alignment) * alignment
+    }
+
     pub async fn to_file(&self,
                          output_path: &Box<Path>,
                          device: &wgpu::Device,
@@ -207,12 +523,18 @@ impl RenderTargetTexture {
             rx.receive().await.unwrap()?;
 
             let data = buffer_slice.get_mapped_range();
+            let unpadded_bytes_per_row = Self::unpadded_bytes_per_row(self.dimensions.width) as usize;
+            let pixels = data
+                .chunks_exact(self.padded_bytes_per_row as usize)
+                .flat_map(|row| &row[..unpadded_bytes_per_row])
+                .copied()
+                .collect::<Vec<u8>>();
 
             use image::{ImageBuffer, Rgba};
             let buffer = ImageBuffer::<Rgba<u8>, _>::from_raw(
                 self.dimensions.width,
                 self.dimensions.height,
-                data,
+                pixels,
             ).unwrap();
             buffer.save(output_path)?;
         }
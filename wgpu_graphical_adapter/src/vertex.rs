@@ -1,7 +1,74 @@
+use cgmath::InnerSpace;
+
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct ModelVertex {
     pub position: [f32; 3],
     pub tex_coords: [f32; 2],
     pub normal: [f32; 3],
-}
\ No newline at end of file
+    pub tangent: [f32; 3],
+    pub bitangent: [f32; 3],
+}
+
+// Computes the tangent and bitangent of every vertex from the triangles it belongs to, averaging
+// them when the vertex is shared. Texture coordinates are expected with their origin at the top left
+// corner (v going down), as the loaders provide them.
+// See https://sotrh.github.io/learn-wgpu/intermediate/tutorial11-normals/#the-tangent-and-the-bitangent
+pub fn compute_tangents(vertices: &mut [ModelVertex], indices: &[u32]) {
+    let mut tangents = vec![cgmath::Vector3::new(0.0f32, 0.0, 0.0); vertices.len()];
+    let mut bitangents = vec![cgmath::Vector3::new(0.0f32, 0.0, 0.0); vertices.len()];
+
+    for triangle in indices.chunks_exact(3) {
+        let v0 = vertices[triangle[0] as usize];
+        let v1 = vertices[triangle[1] as usize];
+        let v2 = vertices[triangle[2] as usize];
+
+        let pos0: cgmath::Vector3<f32> = v0.position.into();
+        let pos1: cgmath::Vector3<f32> = v1.position.into();
+        let pos2: cgmath::Vector3<f32> = v2.position.into();
+
+        let uv0: cgmath::Vector2<f32> = v0.tex_coords.into();
+        let uv1: cgmath::Vector2<f32> = v1.tex_coords.into();
+        let uv2: cgmath::Vector2<f32> = v2.tex_coords.into();
+
+        let delta_pos1 = pos1 - pos0;
+        let delta_pos2 = pos2 - pos0;
+        let delta_uv1 = uv1 - uv0;
+        let delta_uv2 = uv2 - uv0;
+
+        let determinant = delta_uv1.x * delta_uv2.y - delta_uv1.y * delta_uv2.x;
+        if determinant.abs() < f32::EPSILON {
+            // Degenerated texture coordinates, this triangle can't tell anything about the tangent space
+            continue;
+        }
+        let r = 1.0 / determinant;
+        let tangent = (delta_pos1 * delta_uv2.y - delta_pos2 * delta_uv1.y) * r;
+        // Flipped because v goes down while the normal maps green channel points up
+        let bitangent = (delta_pos2 * delta_uv1.x - delta_pos1 * delta_uv2.x) * -r;
+
+        for index in triangle {
+            tangents[*index as usize] += tangent;
+            bitangents[*index as usize] += bitangent;
+        }
+    }
+
+    for (i, vertex) in vertices.iter_mut().enumerate() {
+        let normal: cgmath::Vector3<f32> = vertex.normal.into();
+        if tangents[i].magnitude2() == 0.0 || normal.magnitude2() == 0.0 {
+            continue;
+        }
+        // Gram-Schmidt, keeps the tangent space orthogonal after averaging
+        let tangent = tangents[i] - normal * normal.dot(tangents[i]);
+        if tangent.magnitude2() == 0.0 {
+            continue;
+        }
+        let tangent = tangent.normalize();
+        let bitangent = if bitangents[i].magnitude2() == 0.0 {
+            normal.cross(tangent)
+        } else {
+            bitangents[i].normalize()
+        };
+        vertex.tangent = tangent.into();
+        vertex.bitangent = bitangent.into();
+    }
+}
@@ -1,20 +1,30 @@
 use wgpu::util::DeviceExt;
-use wgpu_graphical_adapter::gateways::WgpuModelLoaderGateway;
-use wgpu_graphical_adapter::instance::Instance;
+use wgpu_graphical_adapter::gateways::{MaterialBindGroupBuilder, WgpuCubemapLoaderGateway, WgpuModelLoaderGateway};
+use wgpu_graphical_adapter::instance::{Instance, InstanceBuffer};
+use wgpu_graphical_adapter::mipmap::MipmapGenerator;
 use wgpu_graphical_adapter::model::{Material, Mesh, Model};
-use wgpu_graphical_adapter::texture::Texture;
-use wgpu_graphical_adapter::vertex::ModelVertex;
+use wgpu_graphical_adapter::texture::{Texture, TextureLoadOptions};
+use wgpu_graphical_adapter::vertex::{compute_tangents, ModelVertex};
 
 pub struct ObjWgpuModelLoaderAdapter {
-    out_dir: Box<str>
+    out_dir: Box<str>,
+    texture_load_options: TextureLoadOptions,
 }
 
 impl ObjWgpuModelLoaderAdapter {
     pub fn new(
         out_dir: Box<str>,
+    ) -> Self {
+        Self::new_with_texture_load_options(out_dir, TextureLoadOptions::default())
+    }
+
+    pub fn new_with_texture_load_options(
+        out_dir: Box<str>,
+        texture_load_options: TextureLoadOptions,
     ) -> Self {
         Self {
-            out_dir
+            out_dir,
+            texture_load_options,
         }
     }
 
@@ -26,11 +36,23 @@ impl ObjWgpuModelLoaderAdapter {
     pub fn load_texture_sync(
         &self,
         file_name: &str,
+        mipmap_generator: &mut MipmapGenerator,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
     ) -> anyhow::Result<Texture> {
         let data = self.load_binary_sync(file_name)?;
-        Texture::new_diffuse_texture_from_bytes(data, device, queue)
+        Texture::new_diffuse_texture_from_bytes(data, self.texture_load_options, mipmap_generator, device, queue)
+    }
+
+    pub fn load_normal_texture_sync(
+        &self,
+        file_name: &str,
+        mipmap_generator: &mut MipmapGenerator,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> anyhow::Result<Texture> {
+        let data = self.load_binary_sync(file_name)?;
+        Texture::new_normal_texture_from_bytes(data, self.texture_load_options, mipmap_generator, device, queue)
     }
 
     fn get_out_dir_path(&self, file_name: &str) -> std::path::PathBuf {
@@ -48,12 +70,8 @@ impl WgpuModelLoaderGateway for ObjWgpuModelLoaderAdapter {
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         texture_bind_group_layout: &wgpu::BindGroupLayout,
-        bind_group_builder: Box<dyn Fn(
-            &wgpu::Device,
-            &wgpu::TextureView,
-            &wgpu::Sampler,
-            &wgpu::BindGroupLayout
-        ) -> wgpu::BindGroup>,
+        bind_group_builder: MaterialBindGroupBuilder,
+        mipmap_generator: &mut MipmapGenerator,
     ) -> anyhow::Result<Model> {
         let (models, obj_materials) = tobj::load_obj(
             self.get_out_dir_path(file_name),
@@ -67,17 +85,22 @@ impl WgpuModelLoaderGateway for ObjWgpuModelLoaderAdapter {
         let mut materials = Vec::new();
         for m in obj_materials? {
             if let Some(diffuse_texture) = &m.diffuse_texture {
-                let diffuse_texture = self.load_texture_sync(&diffuse_texture, device, queue)?;
+                let diffuse_texture = self.load_texture_sync(&diffuse_texture, mipmap_generator, device, queue)?;
+                let normal_texture = match &m.normal_texture {
+                    Some(normal_texture) => Some(self.load_normal_texture_sync(normal_texture, mipmap_generator, device, queue)?),
+                    None => None,
+                };
                 let bind_group = bind_group_builder(
                     device,
-                    &diffuse_texture.view,
-                    &diffuse_texture.sampler,
+                    &diffuse_texture,
+                    normal_texture.as_ref(),
                     texture_bind_group_layout
                 );
 
                 materials.push(Material {
                     name: m.name,
                     diffuse_texture,
+                    normal_texture,
                     bind_group,
                 })
             }
@@ -86,7 +109,7 @@ impl WgpuModelLoaderGateway for ObjWgpuModelLoaderAdapter {
         let meshes = models
             .into_iter()
             .map(|m| {
-                let vertices = (0..m.mesh.positions.len() / 3)
+                let mut vertices = (0..m.mesh.positions.len() / 3)
                     .map(|i| {
                         let position = [
                             m.mesh.positions[i * 3],
@@ -100,6 +123,9 @@ impl WgpuModelLoaderGateway for ObjWgpuModelLoaderAdapter {
                                 position,
                                 tex_coords,
                                 normal: [0.0, 0.0, 0.0],
+                                // Computed below from the triangles
+                                tangent: [0.0, 0.0, 0.0],
+                                bitangent: [0.0, 0.0, 0.0],
                             }
                         } else {
                             ModelVertex {
@@ -110,11 +136,16 @@ impl WgpuModelLoaderGateway for ObjWgpuModelLoaderAdapter {
                                     m.mesh.normals[i * 3 + 1],
                                     m.mesh.normals[i * 3 + 2],
                                 ],
+                                // Computed below from the triangles
+                                tangent: [0.0, 0.0, 0.0],
+                                bitangent: [0.0, 0.0, 0.0],
                             }
                         }
                     })
                     .collect::<Vec<_>>();
 
+                compute_tangents(&mut vertices, &m.mesh.indices);
+
                 let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
                     label: Some(&format!("{:?} Vertex Buffer", file_name)),
                     contents: bytemuck::cast_slice(&vertices),
@@ -137,13 +168,38 @@ impl WgpuModelLoaderGateway for ObjWgpuModelLoaderAdapter {
             .collect::<Vec<_>>();
 
 
-        let instances_buffer = Instance::instances_to_buffer(&instances, device);
+        let instances = InstanceBuffer::new(&instances, device, queue);
 
         Ok(Model { 
             meshes,
             materials,
-            instances: instances_buffer,
-            num_instances: instances.len() as u32
+            instances,
         })
     }
-}
\ No newline at end of file
+}
+
+impl WgpuCubemapLoaderGateway for ObjWgpuModelLoaderAdapter {
+    fn load_cubemap_sync(
+        &self,
+        file_name: &str,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> anyhow::Result<Texture> {
+        let is_equirectangular = std::path::Path::new(file_name)
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("hdr"));
+        if is_equirectangular {
+            let data = self.load_binary_sync(file_name)?;
+            return Texture::new_cubemap_from_equirectangular(data, device, queue);
+        }
+
+        if !file_name.contains("{}") {
+            return Err(anyhow::anyhow!("Cubemap file name must be a .hdr file or contain {{}}: {}", file_name));
+        }
+        let mut faces: [Vec<u8>; 6] = Default::default();
+        for (face, face_name) in faces.iter_mut().zip(["px", "nx", "py", "ny", "pz", "nz"]) {
+            *face = self.load_binary_sync(&file_name.replace("{}", face_name))?;
+        }
+        Texture::new_cubemap_from_faces(faces, device, queue)
+    }
+}
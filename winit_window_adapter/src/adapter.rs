@@ -0,0 +1,173 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use common::Dimentions;
+use common::input::{InputEvent, InputState};
+use common::window::{CursorMode, WindowConfig, WindowMode};
+use common::window_adapter::{WindowAdapter, WindowHandle};
+use winit::application::ApplicationHandler;
+use winit::event::{DeviceEvent, DeviceId, WindowEvent};
+use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop};
+use winit::platform::pump_events::{EventLoopExtPumpEvents, PumpStatus};
+use winit::window::{Window, WindowId};
+
+use crate::window::WinitWindow;
+
+// Drop in replacement for GLFWAdapter, the game owns the loop and the event loop is pumped once per frame.
+// The event loop can't be pumped on the web, iOS and Android, see application::run for those.
+// winit has no gamepad support, no gamepad event is reported.
+pub struct WinitAdapter {
+    event_loop: EventLoop<()>,
+    handler: PumpedHandler,
+    input_state: InputState,
+}
+
+// How long to wait for the event loop to resume and create the window, and for each pump while waiting
+const WINDOW_CREATION_TIMEOUT: Duration = Duration::from_secs(5);
+const WINDOW_CREATION_PUMP_TIMEOUT: Duration = Duration::from_millis(10);
+
+// Receives the events while the event loop is pumped.
+struct PumpedHandler {
+    config: WindowConfig,
+    window: Option<WinitWindow>,
+    error: Option<anyhow::Error>,
+    events: Vec<InputEvent>,
+    close_requested: bool,
+}
+
+impl ApplicationHandler for PumpedHandler {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.window.is_some() || self.error.is_some() {
+            return;
+        }
+        match WinitWindow::new(event_loop, &self.config) {
+            Ok(window) => self.window = Some(window),
+            Err(error) => self.error = Some(error),
+        }
+    }
+
+    fn window_event(&mut self, _event_loop: &ActiveEventLoop, _window_id: WindowId, event: WindowEvent) {
+        if let WindowEvent::CloseRequested = event {
+            self.close_requested = true;
+        }
+        if let Some(window) = &mut self.window {
+            self.events.extend(window.translate_window_event(&event));
+        }
+    }
+
+    fn device_event(&mut self, _event_loop: &ActiveEventLoop, _device_id: DeviceId, event: DeviceEvent) {
+        if let Some(window) = &mut self.window {
+            self.events.extend(window.translate_device_event(&event));
+        }
+    }
+}
+
+impl WinitAdapter {
+    pub fn new(config: &WindowConfig) -> anyhow::Result<WinitAdapter> {
+        log::info!("Initializing winit.");
+
+        let mut event_loop = EventLoop::new().context("Failed to create the winit event loop.")?;
+        event_loop.set_control_flow(ControlFlow::Poll);
+
+        log::info!("winit initialized.");
+
+        let mut handler = PumpedHandler {
+            config: config.clone(),
+            window: None,
+            error: None,
+            events: Vec::new(),
+            close_requested: false,
+        };
+        // The window is created when the event loop resumes, during the first pump on desktop
+        let deadline = Instant::now() + WINDOW_CREATION_TIMEOUT;
+        while handler.window.is_none() {
+            if Instant::now() >= deadline {
+                return Err(anyhow::anyhow!("The winit window was not created after {:?}.", WINDOW_CREATION_TIMEOUT));
+            }
+            if let PumpStatus::Exit(code) = event_loop.pump_app_events(Some(WINDOW_CREATION_PUMP_TIMEOUT), &mut handler) {
+                return Err(anyhow::anyhow!("The winit event loop exited with code {} before the window was created.", code));
+            }
+            if let Some(error) = handler.error.take() {
+                return Err(error);
+            }
+        }
+
+        let mut input_state = InputState::default();
+        input_state.handle_event(&InputEvent::Focused(handler.window().get_window().has_focus()));
+
+        Ok(WinitAdapter {
+            event_loop,
+            handler,
+            input_state,
+        })
+    }
+
+    pub fn get_window(&self) -> Arc<Window> {
+        self.handler.window().get_window()
+    }
+}
+
+impl WindowAdapter for WinitAdapter {
+    fn poll_events(&mut self, handle_event: &mut dyn FnMut(&InputEvent)) {
+        self.input_state.end_frame();
+        if let PumpStatus::Exit(_) = self.event_loop.pump_app_events(Some(Duration::ZERO), &mut self.handler) {
+            self.handler.close_requested = true;
+        }
+        for event in self.handler.events.drain(..) {
+            self.input_state.handle_event(&event);
+            handle_event(&event);
+        }
+    }
+
+    fn get_input_state(&self) -> &InputState {
+        &self.input_state
+    }
+
+    fn should_loop_continue(&mut self) -> bool {
+        !self.handler.close_requested
+    }
+
+    fn request_close(&mut self) {
+        self.handler.close_requested = true;
+    }
+
+    fn get_window_size(&self) -> Dimentions {
+        self.handler.window().get_window_size()
+    }
+
+    fn get_framebuffer_size(&self) -> Dimentions {
+        self.handler.window().get_framebuffer_size()
+    }
+
+    fn get_window_handle(&self) -> Arc<dyn WindowHandle> {
+        self.handler.window().get_window()
+    }
+
+    fn set_title(&mut self, title: &str) -> anyhow::Result<()> {
+        self.handler.window_mut().set_title(title)
+    }
+
+    fn set_cursor_mode(&mut self, cursor_mode: CursorMode) -> anyhow::Result<()> {
+        self.handler.window_mut().set_cursor_mode(cursor_mode)
+    }
+
+    fn set_window_mode(&mut self, window_mode: WindowMode) -> anyhow::Result<()> {
+        self.handler.window_mut().set_window_mode(window_mode)
+    }
+
+    fn get_window_mode(&self) -> WindowMode {
+        self.handler.window().get_window_mode()
+    }
+}
+
+impl PumpedHandler {
+    // Created by WinitAdapter::new
+    fn window(&self) -> &WinitWindow {
+        self.window.as_ref().expect("The winit window is created with the adapter.")
+    }
+
+    fn window_mut(&mut self) -> &mut WinitWindow {
+        self.window.as_mut().expect("The winit window is created with the adapter.")
+    }
+}
@@ -0,0 +1,126 @@
+use anyhow::Context;
+use common::input::{InputEvent, InputState};
+use common::window::WindowConfig;
+use winit::application::ApplicationHandler;
+use winit::event::{DeviceEvent, DeviceId, WindowEvent};
+use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop};
+use winit::window::WindowId;
+
+use crate::window::WinitWindow;
+
+// For the platforms where the event loop can't be pumped, it owns the loop and calls the application back.
+pub trait WinitApplication {
+    // The window is created, the graphics can be initialized with it.
+    fn init(&mut self, window: &mut WinitWindow) -> anyhow::Result<()>;
+
+    // Every event, before it is added to the input state.
+    fn handle_event(&mut self, _event: &InputEvent) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    // Once per frame, with the events received since the last one. Returns false to exit.
+    fn frame(&mut self, window: &mut WinitWindow, input_state: &InputState) -> anyhow::Result<bool>;
+}
+
+struct Runner<A: WinitApplication> {
+    config: WindowConfig,
+    application: A,
+    window: Option<WinitWindow>,
+    input_state: InputState,
+    error: Option<anyhow::Error>,
+}
+
+impl<A: WinitApplication> Runner<A> {
+    fn handle_events(&mut self, event_loop: &ActiveEventLoop, events: Vec<InputEvent>) {
+        for event in events {
+            if let Err(error) = self.application.handle_event(&event) {
+                self.exit(event_loop, error);
+                return;
+            }
+            self.input_state.handle_event(&event);
+        }
+    }
+
+    fn frame(&mut self, event_loop: &ActiveEventLoop) {
+        let Some(window) = &mut self.window else {
+            return;
+        };
+        match self.application.frame(window, &self.input_state) {
+            Ok(true) => {}
+            Ok(false) => event_loop.exit(),
+            Err(error) => self.exit(event_loop, error),
+        }
+        self.input_state.end_frame();
+    }
+
+    fn exit(&mut self, event_loop: &ActiveEventLoop, error: anyhow::Error) {
+        self.error.get_or_insert(error);
+        event_loop.exit();
+    }
+}
+
+impl<A: WinitApplication> ApplicationHandler for Runner<A> {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.window.is_some() {
+            return;
+        }
+        let mut window = match WinitWindow::new(event_loop, &self.config) {
+            Ok(window) => window,
+            Err(error) => return self.exit(event_loop, error),
+        };
+        self.input_state.handle_event(&InputEvent::Focused(window.get_window().has_focus()));
+        let result = self.application.init(&mut window);
+        self.window = Some(window);
+        if let Err(error) = result {
+            self.exit(event_loop, error);
+        }
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _window_id: WindowId, event: WindowEvent) {
+        if let WindowEvent::RedrawRequested = event {
+            return self.frame(event_loop);
+        }
+        let Some(window) = &mut self.window else {
+            return;
+        };
+        let events = window.translate_window_event(&event);
+        self.handle_events(event_loop, events);
+        if let WindowEvent::CloseRequested = event {
+            event_loop.exit();
+        }
+    }
+
+    fn device_event(&mut self, event_loop: &ActiveEventLoop, _device_id: DeviceId, event: DeviceEvent) {
+        let Some(window) = &mut self.window else {
+            return;
+        };
+        let events = window.translate_device_event(&event).into_iter().collect();
+        self.handle_events(event_loop, events);
+    }
+
+    // Renders continuously, like the polled loop
+    fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
+        if let Some(window) = &self.window {
+            window.get_window().request_redraw();
+        }
+    }
+}
+
+// Runs the application until it returns false from frame, the window is closed or an error occurs.
+pub fn run<A: WinitApplication>(config: &WindowConfig, application: A) -> anyhow::Result<()> {
+    let event_loop = EventLoop::new().context("Failed to create the winit event loop.")?;
+    event_loop.set_control_flow(ControlFlow::Poll);
+
+    let mut runner = Runner {
+        config: config.clone(),
+        application,
+        window: None,
+        input_state: InputState::default(),
+        error: None,
+    };
+    event_loop.run_app(&mut runner).context("The winit event loop failed.")?;
+    match runner.error {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
+}
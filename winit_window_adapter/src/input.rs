@@ -0,0 +1,200 @@
+use common::input::{InputEvent, Key, MouseButton};
+use common::window::CursorMode;
+use common::Dimentions;
+use winit::event::{DeviceEvent, ElementState, Ime, MouseScrollDelta, WindowEvent};
+use winit::keyboard::{KeyCode, PhysicalKey};
+
+// winit reports touchpad scrolling in pixels, InputEvent::Scrolled is in lines
+const PIXELS_PER_LINE: f32 = 20.0;
+
+// Turns winit events into window agnostic ones.
+// It keeps the cursor mode, as with a disabled cursor the position is made up from the raw mouse motion,
+// winit doesn't move a locked cursor.
+pub struct EventTranslator {
+    cursor_mode: CursorMode,
+    cursor_position: (f32, f32),
+}
+
+impl EventTranslator {
+    pub fn new(cursor_mode: CursorMode) -> EventTranslator {
+        EventTranslator {
+            cursor_mode,
+            cursor_position: (0.0, 0.0),
+        }
+    }
+
+    pub fn set_cursor_mode(&mut self, cursor_mode: CursorMode) {
+        self.cursor_mode = cursor_mode;
+    }
+
+    // Empty for the events the game doesn't need, a key press may also come with text.
+    pub fn translate_window_event(&mut self, event: &WindowEvent) -> Vec<InputEvent> {
+        match event {
+            WindowEvent::KeyboardInput { event, .. } => {
+                let mut events = Vec::new();
+                // Held keys are tracked by InputState, the repeats are only useful for text
+                if !event.repeat {
+                    let key = translate_key(event.physical_key);
+                    events.push(match event.state {
+                        ElementState::Pressed => InputEvent::KeyPressed(key),
+                        ElementState::Released => InputEvent::KeyReleased(key),
+                    });
+                }
+                if event.state == ElementState::Pressed {
+                    // Unlike GLFW, winit also reports the control characters of keys like enter and backspace as text
+                    if let Some(text) = &event.text {
+                        events.extend(text.chars().filter(|character| !character.is_control()).map(InputEvent::Text));
+                    }
+                }
+                events
+            }
+            WindowEvent::Ime(Ime::Commit(text)) => text.chars().map(InputEvent::Text).collect(),
+            WindowEvent::MouseInput { state, button, .. } => {
+                let button = translate_mouse_button(*button);
+                vec![match state {
+                    ElementState::Pressed => InputEvent::MouseButtonPressed(button),
+                    ElementState::Released => InputEvent::MouseButtonReleased(button),
+                }]
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                if self.cursor_mode == CursorMode::Disabled {
+                    return Vec::new();
+                }
+                self.cursor_position = (position.x as f32, position.y as f32);
+                vec![InputEvent::CursorMoved { x: self.cursor_position.0, y: self.cursor_position.1 }]
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let (x, y) = match *delta {
+                    MouseScrollDelta::LineDelta(x, y) => (x, y),
+                    MouseScrollDelta::PixelDelta(position) => {
+                        (position.x as f32 / PIXELS_PER_LINE, position.y as f32 / PIXELS_PER_LINE)
+                    }
+                };
+                vec![InputEvent::Scrolled { x, y }]
+            }
+            WindowEvent::Focused(focused) => vec![InputEvent::Focused(*focused)],
+            WindowEvent::Resized(size) => vec![InputEvent::Resized(Dimentions {
+                width: size.width,
+                height: size.height,
+            })],
+            WindowEvent::CloseRequested => vec![InputEvent::CloseRequested],
+            _ => Vec::new(),
+        }
+    }
+
+    // The raw mouse motion moves the cursor only while it is disabled, otherwise CursorMoved does.
+    pub fn translate_device_event(&mut self, event: &DeviceEvent) -> Option<InputEvent> {
+        match *event {
+            DeviceEvent::MouseMotion { delta: (x, y) } if self.cursor_mode == CursorMode::Disabled => {
+                self.cursor_position.0 += x as f32;
+                self.cursor_position.1 += y as f32;
+                Some(InputEvent::CursorMoved { x: self.cursor_position.0, y: self.cursor_position.1 })
+            }
+            _ => None,
+        }
+    }
+}
+
+fn translate_mouse_button(button: winit::event::MouseButton) -> MouseButton {
+    match button {
+        winit::event::MouseButton::Left => MouseButton::Left,
+        winit::event::MouseButton::Right => MouseButton::Right,
+        winit::event::MouseButton::Middle => MouseButton::Middle,
+        // Numbered like the GLFW buttons 4 and 5
+        winit::event::MouseButton::Back => MouseButton::Other(4),
+        winit::event::MouseButton::Forward => MouseButton::Other(5),
+        winit::event::MouseButton::Other(other) => MouseButton::Other(u8::try_from(other).unwrap_or(u8::MAX)),
+    }
+}
+
+fn translate_key(key: PhysicalKey) -> Key {
+    let PhysicalKey::Code(code) = key else {
+        return Key::Unknown;
+    };
+    match code {
+        KeyCode::KeyA => Key::A,
+        KeyCode::KeyB => Key::B,
+        KeyCode::KeyC => Key::C,
+        KeyCode::KeyD => Key::D,
+        KeyCode::KeyE => Key::E,
+        KeyCode::KeyF => Key::F,
+        KeyCode::KeyG => Key::G,
+        KeyCode::KeyH => Key::H,
+        KeyCode::KeyI => Key::I,
+        KeyCode::KeyJ => Key::J,
+        KeyCode::KeyK => Key::K,
+        KeyCode::KeyL => Key::L,
+        KeyCode::KeyM => Key::M,
+        KeyCode::KeyN => Key::N,
+        KeyCode::KeyO => Key::O,
+        KeyCode::KeyP => Key::P,
+        KeyCode::KeyQ => Key::Q,
+        KeyCode::KeyR => Key::R,
+        KeyCode::KeyS => Key::S,
+        KeyCode::KeyT => Key::T,
+        KeyCode::KeyU => Key::U,
+        KeyCode::KeyV => Key::V,
+        KeyCode::KeyW => Key::W,
+        KeyCode::KeyX => Key::X,
+        KeyCode::KeyY => Key::Y,
+        KeyCode::KeyZ => Key::Z,
+        KeyCode::Digit0 => Key::Num0,
+        KeyCode::Digit1 => Key::Num1,
+        KeyCode::Digit2 => Key::Num2,
+        KeyCode::Digit3 => Key::Num3,
+        KeyCode::Digit4 => Key::Num4,
+        KeyCode::Digit5 => Key::Num5,
+        KeyCode::Digit6 => Key::Num6,
+        KeyCode::Digit7 => Key::Num7,
+        KeyCode::Digit8 => Key::Num8,
+        KeyCode::Digit9 => Key::Num9,
+        KeyCode::F1 => Key::F1,
+        KeyCode::F2 => Key::F2,
+        KeyCode::F3 => Key::F3,
+        KeyCode::F4 => Key::F4,
+        KeyCode::F5 => Key::F5,
+        KeyCode::F6 => Key::F6,
+        KeyCode::F7 => Key::F7,
+        KeyCode::F8 => Key::F8,
+        KeyCode::F9 => Key::F9,
+        KeyCode::F10 => Key::F10,
+        KeyCode::F11 => Key::F11,
+        KeyCode::F12 => Key::F12,
+        KeyCode::ArrowUp => Key::Up,
+        KeyCode::ArrowDown => Key::Down,
+        KeyCode::ArrowLeft => Key::Left,
+        KeyCode::ArrowRight => Key::Right,
+        KeyCode::Space => Key::Space,
+        KeyCode::Enter => Key::Enter,
+        KeyCode::Escape => Key::Escape,
+        KeyCode::Tab => Key::Tab,
+        KeyCode::Backspace => Key::Backspace,
+        KeyCode::Insert => Key::Insert,
+        KeyCode::Delete => Key::Delete,
+        KeyCode::Home => Key::Home,
+        KeyCode::End => Key::End,
+        KeyCode::PageUp => Key::PageUp,
+        KeyCode::PageDown => Key::PageDown,
+        KeyCode::CapsLock => Key::CapsLock,
+        KeyCode::ShiftLeft => Key::LeftShift,
+        KeyCode::ShiftRight => Key::RightShift,
+        KeyCode::ControlLeft => Key::LeftControl,
+        KeyCode::ControlRight => Key::RightControl,
+        KeyCode::AltLeft => Key::LeftAlt,
+        KeyCode::AltRight => Key::RightAlt,
+        KeyCode::SuperLeft => Key::LeftSuper,
+        KeyCode::SuperRight => Key::RightSuper,
+        KeyCode::Minus => Key::Minus,
+        KeyCode::Equal => Key::Equal,
+        KeyCode::BracketLeft => Key::LeftBracket,
+        KeyCode::BracketRight => Key::RightBracket,
+        KeyCode::Backslash => Key::Backslash,
+        KeyCode::Semicolon => Key::Semicolon,
+        KeyCode::Quote => Key::Apostrophe,
+        KeyCode::Comma => Key::Comma,
+        KeyCode::Period => Key::Period,
+        KeyCode::Slash => Key::Slash,
+        KeyCode::Backquote => Key::GraveAccent,
+        _ => Key::Unknown,
+    }
+}
@@ -0,0 +1,4 @@
+pub mod adapter;
+pub mod application;
+pub mod input;
+pub mod window;
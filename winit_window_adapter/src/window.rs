@@ -0,0 +1,159 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use common::Dimentions;
+use common::input::InputEvent;
+use common::window::{CursorMode, WindowConfig, WindowMode};
+use winit::dpi::LogicalSize;
+use winit::event::{DeviceEvent, WindowEvent};
+use winit::event_loop::ActiveEventLoop;
+use winit::monitor::{MonitorHandle, VideoModeHandle};
+use winit::window::{CursorGrabMode, Fullscreen, Icon, Window};
+
+use crate::input::EventTranslator;
+
+// The window of both adapters, with the runtime changes of GLFWAdapter.
+pub struct WinitWindow {
+    window: Arc<Window>,
+    translator: EventTranslator,
+    window_mode: WindowMode,
+    // The size of the exclusive fullscreen mode
+    fullscreen_size: Dimentions,
+}
+
+impl WinitWindow {
+    // The window can only be created once the event loop runs.
+    pub(crate) fn new(event_loop: &ActiveEventLoop, config: &WindowConfig) -> anyhow::Result<WinitWindow> {
+        let mut attributes = Window::default_attributes()
+            .with_title(config.title.clone())
+            .with_inner_size(LogicalSize::new(config.size.width, config.size.height))
+            .with_resizable(config.resizable)
+            .with_decorations(config.decorated);
+        if let Some(size) = config.min_size {
+            attributes = attributes.with_min_inner_size(LogicalSize::new(size.width, size.height));
+        }
+        if let Some(size) = config.max_size {
+            attributes = attributes.with_max_inner_size(LogicalSize::new(size.width, size.height));
+        }
+        if let Some(icon) = &config.icon {
+            let icon = Icon::from_rgba(icon.rgba.clone(), icon.width, icon.height)
+                .context("Failed to create the window icon.")?;
+            attributes = attributes.with_window_icon(Some(icon));
+        }
+
+        log::info!("Creating winit window.");
+
+        // Created windowed, the fullscreen modes are applied like they are at runtime
+        let window = event_loop.create_window(attributes).context("Failed to create winit window.")?;
+
+        log::info!("winit window created.");
+
+        let mut window = WinitWindow {
+            window: Arc::new(window),
+            translator: EventTranslator::new(config.cursor_mode),
+            window_mode: WindowMode::Windowed,
+            fullscreen_size: config.size,
+        };
+        window.set_cursor_mode(config.cursor_mode)?;
+        window.set_window_mode(config.mode)?;
+        Ok(window)
+    }
+
+    pub(crate) fn translate_window_event(&mut self, event: &WindowEvent) -> Vec<InputEvent> {
+        self.translator.translate_window_event(event)
+    }
+
+    pub(crate) fn translate_device_event(&mut self, event: &DeviceEvent) -> Option<InputEvent> {
+        self.translator.translate_device_event(event)
+    }
+
+    pub fn set_title(&mut self, title: &str) -> anyhow::Result<()> {
+        self.window.set_title(title);
+        Ok(())
+    }
+
+    pub fn set_cursor_mode(&mut self, cursor_mode: CursorMode) -> anyhow::Result<()> {
+        match cursor_mode {
+            CursorMode::Normal | CursorMode::Hidden => {
+                self.window.set_cursor_grab(CursorGrabMode::None).context("Failed to release the cursor.")?;
+            }
+            CursorMode::Disabled => {
+                // Each platform supports only one of the grab modes, confined still keeps the cursor in the window
+                self.window
+                    .set_cursor_grab(CursorGrabMode::Locked)
+                    .or_else(|_| self.window.set_cursor_grab(CursorGrabMode::Confined))
+                    .context("Failed to grab the cursor.")?;
+            }
+        }
+        self.window.set_cursor_visible(cursor_mode == CursorMode::Normal);
+        self.translator.set_cursor_mode(cursor_mode);
+        Ok(())
+    }
+
+    // Switches between windowed and fullscreen, winit gives the window back its position and size when leaving fullscreen.
+    pub fn set_window_mode(&mut self, window_mode: WindowMode) -> anyhow::Result<()> {
+        if window_mode == self.window_mode {
+            return Ok(());
+        }
+        let fullscreen = match window_mode {
+            WindowMode::Windowed => None,
+            WindowMode::Fullscreen { monitor } => {
+                let monitor = self.get_monitor(monitor)?;
+                Some(Fullscreen::Exclusive(Self::get_video_mode(&monitor, self.fullscreen_size)?))
+            }
+            WindowMode::BorderlessFullscreen { monitor } => Some(Fullscreen::Borderless(Some(self.get_monitor(monitor)?))),
+        };
+        self.window.set_fullscreen(fullscreen);
+        self.window_mode = window_mode;
+        Ok(())
+    }
+
+    pub fn get_window_mode(&self) -> WindowMode {
+        self.window_mode
+    }
+
+    pub fn get_window(&self) -> Arc<Window> {
+        self.window.clone()
+    }
+
+    pub fn get_window_size(&self) -> Dimentions {
+        let size = self.window.inner_size().to_logical::<u32>(self.window.scale_factor());
+        Dimentions {
+            width: size.width,
+            height: size.height,
+        }
+    }
+
+    // The framebuffer size is in pixels and may differ from the window size on high DPI screens,
+    // this is the size the surface should be configured with.
+    pub fn get_framebuffer_size(&self) -> Dimentions {
+        let size = self.window.inner_size();
+        Dimentions {
+            width: size.width,
+            height: size.height,
+        }
+    }
+
+    // The primary monitor is 0, like with GLFW
+    fn get_monitor(&self, index: usize) -> anyhow::Result<MonitorHandle> {
+        let primary = self.window.primary_monitor();
+        let others = self.window.available_monitors().filter(|monitor| Some(monitor) != primary.as_ref());
+        let monitors: Vec<MonitorHandle> = primary.clone().into_iter().chain(others).collect();
+        let count = monitors.len();
+        monitors
+            .into_iter()
+            .nth(index)
+            .ok_or_else(|| anyhow::anyhow!("No monitor {}, {} connected.", index, count))
+    }
+
+    // The closest to the requested size, with the highest refresh rate
+    fn get_video_mode(monitor: &MonitorHandle, size: Dimentions) -> anyhow::Result<VideoModeHandle> {
+        monitor
+            .video_modes()
+            .min_by_key(|mode| {
+                let difference = mode.size().width.abs_diff(size.width) + mode.size().height.abs_diff(size.height);
+                (difference, std::cmp::Reverse(mode.refresh_rate_millihertz()))
+            })
+            .context("The monitor has no video mode.")
+    }
+}